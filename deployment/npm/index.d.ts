@@ -1,2 +1,38 @@
 /** Gets an absolute path to the Wasm module. */
 export function getPath(): string;
+
+/** A problem with a configuration property, which uses its default value instead. */
+export interface ConfigurationDiagnostic {
+  propertyName: string;
+  message: string;
+}
+
+export interface FormatResult {
+  /** The formatted text, which is the provided text when it was already formatted. */
+  text: string;
+  /** Whether the formatted text differs from the provided text. */
+  changed: boolean;
+  diagnostics: ConfigurationDiagnostic[];
+}
+
+/**
+ * Formats a file with the Wasm module.
+ *
+ * Embedded languages (ex. CSS in tagged templates) are left as-is.
+ *
+ * @param filePath - Path of the file, which determines how the text is parsed (ex. `file.tsx`).
+ * @param text - Text of the file.
+ * @param config - The plugin configuration, which is the "typescript" property of a dprint configuration file
+ * (ex. `{ lineWidth: 80, semiColons: "asi" }`).
+ * @throws When the file fails to parse.
+ */
+export function format(filePath: string, text: string, config?: Record<string, unknown>): FormatResult;
+
+/**
+ * Releases the Wasm module loaded by `format` along with its configurations.
+ *
+ * Only the 10 most recently used configurations are kept, so this is only
+ * necessary to free the memory once done formatting. Calling `format` afterwards
+ * loads the Wasm module again.
+ */
+export function dispose(): void;
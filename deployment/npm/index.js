@@ -1,11 +1,139 @@
+// @ts-check
+const fs = require("fs");
+const path = require("path");
+
 /**
  * Gets the path to the Wasm module.
  * @returns {string}
  */
 function getPath() {
-  return require("path").join(__dirname, "plugin.wasm");
+  return path.join(__dirname, "plugin.wasm");
+}
+
+/** The number of configurations to keep registered with the Wasm module. */
+const maxConfigs = 10;
+
+/** @type {WasmPlugin | undefined} */
+let plugin;
+
+/**
+ * Formats a file with the Wasm module.
+ * @param {string} filePath
+ * @param {string} text
+ * @param {Record<string, unknown>} [config]
+ * @returns {import("./index").FormatResult}
+ */
+function format(filePath, text, config = {}) {
+  plugin ??= new WasmPlugin(fs.readFileSync(getPath()));
+  return plugin.format(filePath, text, config);
+}
+
+/** Releases the Wasm module and its configurations. `format` loads it again when called. */
+function dispose() {
+  plugin?.dispose();
+  plugin = undefined;
+}
+
+/** Communicates with the Wasm module using the dprint Wasm plugin schema (version 4). */
+class WasmPlugin {
+  /**
+   * The registered configuration ids by configuration, from least to most recently used.
+   * @type {Map<string, number>}
+   */
+  #configIds = new Map();
+  #nextConfigId = 1;
+  /** @type {any} */
+  #exports;
+
+  /** @param {Uint8Array} wasmBytes */
+  constructor(wasmBytes) {
+    const wasmModule = new WebAssembly.Module(wasmBytes);
+    const instance = new WebAssembly.Instance(wasmModule, {
+      dprint: {
+        // there are no other plugins, so the host never changes embedded languages
+        host_format: () => 0,
+        host_get_formatted_text: () => 0,
+        host_get_error_text: () => 0,
+        host_write_buffer: () => {},
+        host_has_cancelled: () => 0,
+      },
+    });
+    this.#exports = instance.exports;
+    if (typeof this.#exports.dprint_plugin_version_4 !== "function") {
+      throw new Error("The Wasm module does not support dprint plugin schema version 4.");
+    }
+  }
+
+  /**
+   * @param {string} filePath
+   * @param {string} text
+   * @param {Record<string, unknown>} config
+   * @returns {import("./index").FormatResult}
+   */
+  format(filePath, text, config) {
+    const configId = this.#getConfigId(config);
+    this.#sendString(filePath);
+    this.#exports.set_file_path();
+    this.#sendString(text);
+    const result = this.#exports.format(configId);
+    const diagnostics = JSON.parse(this.#receiveString(this.#exports.get_config_diagnostics(configId)));
+    switch (result) {
+      case 0:
+        return { text, changed: false, diagnostics };
+      case 1:
+        return { text: this.#receiveString(this.#exports.get_formatted_text()), changed: true, diagnostics };
+      case 2:
+        throw new Error(this.#receiveString(this.#exports.get_error_text()));
+      default:
+        throw new Error(`Unknown format result: ${result}`);
+    }
+  }
+
+  /** @param {Record<string, unknown>} config */
+  #getConfigId(config) {
+    const key = JSON.stringify(config);
+    let configId = this.#configIds.get(key);
+    if (configId == null) {
+      if (this.#configIds.size >= maxConfigs) {
+        const [oldestKey, oldestConfigId] = /** @type {[string, number]} */ (this.#configIds.entries().next().value);
+        this.#exports.release_config(oldestConfigId);
+        this.#configIds.delete(oldestKey);
+      }
+      configId = this.#nextConfigId++;
+      this.#sendString(JSON.stringify({ plugin: config, global: {} }));
+      this.#exports.register_config(configId);
+    } else {
+      // move it to the end as the most recently used
+      this.#configIds.delete(key);
+    }
+    this.#configIds.set(key, configId);
+    return configId;
+  }
+
+  /** Releases the registered configurations. */
+  dispose() {
+    for (const configId of this.#configIds.values()) {
+      this.#exports.release_config(configId);
+    }
+    this.#configIds.clear();
+  }
+
+  /** @param {string} text */
+  #sendString(text) {
+    const bytes = new TextEncoder().encode(text);
+    const pointer = this.#exports.clear_shared_bytes(bytes.length);
+    new Uint8Array(this.#exports.memory.buffer, pointer, bytes.length).set(bytes);
+  }
+
+  /** @param {number} length */
+  #receiveString(length) {
+    const pointer = this.#exports.get_shared_bytes_ptr();
+    return new TextDecoder().decode(new Uint8Array(this.#exports.memory.buffer, pointer, length));
+  }
 }
 
 module.exports = {
   getPath,
+  format,
+  dispose,
 };
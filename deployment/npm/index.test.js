@@ -1,7 +1,7 @@
 // @ts-check
 const assert = require("assert");
 const createFromBuffer = require("@dprint/formatter").createFromBuffer;
-const getPath = require("./index").getPath;
+const { getPath, format, dispose } = require("./index");
 
 const buffer = require("fs").readFileSync(getPath());
 const formatter = createFromBuffer(buffer);
@@ -11,3 +11,26 @@ const result = formatter.formatText({
 });
 
 assert.strictEqual(result, "const t = 5;\n");
+
+// format api
+assert.deepStrictEqual(format("file.ts", "const   t   = 5"), { text: "const t = 5;\n", changed: true, diagnostics: [] });
+assert.deepStrictEqual(format("file.ts", "const t = 5;\n"), { text: "const t = 5;\n", changed: false, diagnostics: [] });
+assert.deepStrictEqual(format("file.tsx", "const a = <div>{ '🚀' }</div>", { semiColons: "asi", unknown: 1 }), {
+  text: "const a = <div>{\"🚀\"}</div>\n",
+  changed: true,
+  diagnostics: [{ propertyName: "unknown", message: "Unknown property in configuration" }],
+});
+assert.throws(() => format("file.ts", "const a = ;"), /Expression expected/);
+
+// configurations past the most recently used ones are released
+/** @param {number} indentWidth */
+function formatWithIndentWidth(indentWidth) {
+  return format("file.ts", "if (a) {\nb;\n}", { indentWidth }).text;
+}
+for (let indentWidth = 1; indentWidth <= 12; indentWidth++) {
+  assert.strictEqual(formatWithIndentWidth(indentWidth), `if (a) {\n${" ".repeat(indentWidth)}b;\n}\n`);
+}
+assert.strictEqual(formatWithIndentWidth(12), `if (a) {\n${" ".repeat(12)}b;\n}\n`);
+assert.strictEqual(formatWithIndentWidth(1), "if (a) {\n b;\n}\n");
+dispose();
+assert.strictEqual(format("file.ts", "const   t   = 5").text, "const t = 5;\n");
@@ -0,0 +1,93 @@
+/*
+ * C API of the dprint-plugin-typescript dynamic library, which is built with
+ * `cargo build --profile release-ffi --features ffi`.
+ *
+ * Strings are UTF-8. Functions that fail store an error message that can be
+ * retrieved with `dprint_ts_last_error` on the same thread.
+ */
+
+#ifndef DPRINT_PLUGIN_TYPESCRIPT_H
+#define DPRINT_PLUGIN_TYPESCRIPT_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* The text was already formatted. */
+#define DPRINT_TS_UNCHANGED 0
+/* The text was formatted. */
+#define DPRINT_TS_CHANGED 1
+/* Formatting failed. See `dprint_ts_last_error`. */
+#define DPRINT_TS_ERROR (-1)
+
+typedef struct DprintTsConfig DprintTsConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a configuration from the UTF-8 JSON text of a dprint configuration file or
+ * the plugin configuration.
+ *
+ * Returns null on error. Free the configuration with `dprint_ts_config_free`.
+ *
+ * # Safety
+ *
+ * `json_text` must point to `json_text_len` readable bytes.
+ */
+struct DprintTsConfig *dprint_ts_config_new(const uint8_t *json_text, size_t json_text_len);
+
+/**
+ * Frees a configuration created by `dprint_ts_config_new`.
+ *
+ * # Safety
+ *
+ * `config` must be null or a configuration from `dprint_ts_config_new` that wasn't freed.
+ */
+void dprint_ts_config_free(struct DprintTsConfig *config);
+
+/**
+ * Formats the UTF-8 text of a file. The file path (ex. `file.tsx`) determines how the text is parsed.
+ *
+ * Returns `DPRINT_TS_CHANGED` and sets `out_text` and `out_text_len` to the formatted text, which
+ * must be freed with `dprint_ts_text_free`, `DPRINT_TS_UNCHANGED` when the text was already formatted,
+ * or `DPRINT_TS_ERROR` on error.
+ *
+ * The configuration may be used from multiple threads at the same time.
+ *
+ * # Safety
+ *
+ * `config` must be a configuration from `dprint_ts_config_new`, `file_path` a null terminated
+ * string, `text` must point to `text_len` readable bytes and `out_text` and `out_text_len` must be writable.
+ */
+int32_t dprint_ts_format(const struct DprintTsConfig *config,
+                         const char *file_path,
+                         const uint8_t *text,
+                         size_t text_len,
+                         uint8_t **out_text,
+                         size_t *out_text_len);
+
+/**
+ * Frees the formatted text from `dprint_ts_format`.
+ *
+ * # Safety
+ *
+ * `text` and `text_len` must be null and zero or the formatted text from `dprint_ts_format` that wasn't freed.
+ */
+void dprint_ts_text_free(uint8_t *text,
+                         size_t text_len);
+
+/**
+ * Gets the error message of the last call on the current thread that failed or null if it succeeded.
+ *
+ * The message is valid until the next call on the current thread and must not be freed.
+ */
+const char *dprint_ts_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DPRINT_PLUGIN_TYPESCRIPT_H */
@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use deno_ast::swc::ast::*;
+use deno_ast::swc::common::comments::CommentKind;
+use deno_ast::swc::common::util::take::Take;
+use deno_ast::swc::common::EqIgnoreSpan;
+use deno_ast::swc::common::DUMMY_SP;
+use deno_ast::swc::ecma_visit::VisitMut;
+use deno_ast::swc::ecma_visit::VisitMutWith;
+use deno_ast::MultiThreadedComments;
+use deno_ast::ParsedSource;
+use deno_ast::SourceRange;
+use deno_ast::SourceRangedForSpanned;
+
+use super::error::ErrorLocation;
+use super::error::FormatError;
+use super::swc::reparse_swc_ast;
+use super::utils::get_language_from_comment_hint;
+use super::utils::get_language_from_tag_path;
+
+/// Ensures the formatted text has the same syntax tree as the original text.
+///
+/// Spans, comments and the changes the formatter is allowed to make (ex. removing
+/// parentheses, adding braces, sorting imports, or JSX whitespace) are ignored.
+/// The text of embedded languages is ignored when there is an external formatter,
+/// which provides the tagged template languages.
+pub fn ensure_ast_equivalence(original: &ParsedSource, formatted_text: &str, embedded_languages: Option<&BTreeMap<String, String>>) -> Result<()> {
+  let formatted = match reparse_swc_ast(original, formatted_text.into()) {
+    Ok(formatted) => formatted,
+    Err(err) => {
+      return Err(
+        FormatError::Internal {
+          location: None,
+          message: format!("The formatted text failed to parse. This is a bug in the formatter.\n\n{:#}", err),
+        }
+        .into(),
+      )
+    }
+  };
+
+  let mut original_program = (*original.program()).clone();
+  let mut formatted_program = (*formatted.program()).clone();
+  original_program.visit_mut_with(&mut Normalizer {
+    embedded_languages,
+    comments: original.comments(),
+  });
+  formatted_program.visit_mut_with(&mut Normalizer {
+    embedded_languages,
+    comments: formatted.comments(),
+  });
+  if original_program.eq_ignore_span(&formatted_program) {
+    return Ok(());
+  }
+
+  let location = get_first_different_item_range(&original_program, &formatted_program).map(|range| {
+    let position = original.text_info_lazy().line_and_column_display(range.start);
+    ErrorLocation {
+      specifier: original.specifier().clone(),
+      line_number: position.line_number,
+      column_number: position.column_number,
+      range: range.as_byte_range(original.range().start),
+    }
+  });
+  Err(
+    FormatError::Internal {
+      location,
+      message: "Formatting changed the syntax tree of the code. This is a bug in the formatter.".to_string(),
+    }
+    .into(),
+  )
+}
+
+fn get_first_different_item_range(original: &Program, formatted: &Program) -> Option<SourceRange> {
+  match (original, formatted) {
+    (Program::Module(original), Program::Module(formatted)) => get_first_different_range(&original.body, &formatted.body),
+    (Program::Script(original), Program::Script(formatted)) => get_first_different_range(&original.body, &formatted.body),
+    _ => None,
+  }
+}
+
+fn get_first_different_range<T: EqIgnoreSpan + SourceRangedForSpanned>(original: &[T], formatted: &[T]) -> Option<SourceRange> {
+  original
+    .iter()
+    .enumerate()
+    .find(|(i, item)| formatted.get(*i).map(|other| !(*item).eq_ignore_span(other)).unwrap_or(true))
+    .map(|(_, item)| item.range())
+}
+
+/// Normalizes the differences in the syntax tree that the formatter is allowed to make.
+struct Normalizer<'a> {
+  /// The tagged template languages when the external formatter may change embedded languages.
+  embedded_languages: Option<&'a BTreeMap<String, String>>,
+  comments: &'a MultiThreadedComments,
+}
+
+impl VisitMut for Normalizer<'_> {
+  fn visit_mut_expr(&mut self, expr: &mut Expr) {
+    while let Expr::Paren(paren_expr) = expr {
+      *expr = *paren_expr.expr.take();
+    }
+    expr.visit_mut_children_with(self);
+  }
+
+  fn visit_mut_ts_type(&mut self, ts_type: &mut TsType) {
+    while let TsType::TsParenthesizedType(paren_type) = ts_type {
+      let inner_type = std::mem::replace(&mut *paren_type.type_ann, TsType::TsThisType(TsThisType { span: DUMMY_SP }));
+      *ts_type = inner_type;
+    }
+    ts_type.visit_mut_children_with(self);
+  }
+
+  fn visit_mut_new_expr(&mut self, node: &mut NewExpr) {
+    node.visit_mut_children_with(self);
+    // the formatter adds parentheses to `new A`
+    if node.args.is_none() {
+      node.args = Some(Vec::new());
+    }
+  }
+
+  fn visit_mut_prop(&mut self, prop: &mut Prop) {
+    prop.visit_mut_children_with(self);
+    // the formatter turns the invalid `{ a = 5 }` into `{ a: 5 }`
+    if let Prop::Assign(assign) = prop {
+      *prop = Prop::KeyValue(KeyValueProp {
+        key: PropName::Str(ident_to_str(assign.key.sym.clone())),
+        value: assign.value.take(),
+      });
+    }
+  }
+
+  fn visit_mut_prop_name(&mut self, prop_name: &mut PropName) {
+    prop_name.visit_mut_children_with(self);
+    // the formatter may add or remove the quotes of property names
+    if let PropName::Ident(ident) = prop_name {
+      *prop_name = PropName::Str(ident_to_str(ident.sym.clone()));
+    }
+  }
+
+  fn visit_mut_ts_type_element(&mut self, element: &mut TsTypeElement) {
+    element.visit_mut_children_with(self);
+    let (key, computed) = match element {
+      TsTypeElement::TsPropertySignature(signature) => (&mut signature.key, signature.computed),
+      TsTypeElement::TsMethodSignature(signature) => (&mut signature.key, signature.computed),
+      TsTypeElement::TsGetterSignature(signature) => (&mut signature.key, signature.computed),
+      TsTypeElement::TsSetterSignature(signature) => (&mut signature.key, signature.computed),
+      _ => return,
+    };
+    if !computed {
+      if let Expr::Ident(ident) = &**key {
+        **key = Expr::Lit(Lit::Str(ident_to_str(ident.sym.clone())));
+      }
+    }
+  }
+
+  fn visit_mut_if_stmt(&mut self, node: &mut IfStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.cons);
+    if let Some(alt) = &mut node.alt {
+      unwrap_single_stmt_block(alt);
+    }
+  }
+
+  fn visit_mut_for_stmt(&mut self, node: &mut ForStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.body);
+  }
+
+  fn visit_mut_for_in_stmt(&mut self, node: &mut ForInStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.body);
+  }
+
+  fn visit_mut_for_of_stmt(&mut self, node: &mut ForOfStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.body);
+  }
+
+  fn visit_mut_while_stmt(&mut self, node: &mut WhileStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.body);
+  }
+
+  fn visit_mut_do_while_stmt(&mut self, node: &mut DoWhileStmt) {
+    node.visit_mut_children_with(self);
+    unwrap_single_stmt_block(&mut node.body);
+  }
+
+  fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+    stmts.visit_mut_children_with(self);
+    // the formatter removes empty statements
+    stmts.retain(|stmt| !matches!(stmt, Stmt::Empty(_)));
+  }
+
+  fn visit_mut_class_members(&mut self, members: &mut Vec<ClassMember>) {
+    members.visit_mut_children_with(self);
+    members.retain(|member| !matches!(member, ClassMember::Empty(_)));
+  }
+
+  fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+    items.visit_mut_children_with(self);
+    items.retain(|item| !matches!(item, ModuleItem::Stmt(Stmt::Empty(_))));
+    // the order of consecutive imports and re-exports may change when sorting
+    sort_consecutive(items, get_import_sort_key);
+    sort_consecutive(items, get_re_export_sort_key);
+  }
+
+  fn visit_mut_import_decl(&mut self, node: &mut ImportDecl) {
+    node.visit_mut_children_with(self);
+    // the formatter removes redundant type keywords of specifiers
+    if node.type_only {
+      for specifier in node.specifiers.iter_mut() {
+        if let ImportSpecifier::Named(named) = specifier {
+          named.is_type_only = false;
+        }
+      }
+    }
+  }
+
+  fn visit_mut_named_export(&mut self, node: &mut NamedExport) {
+    node.visit_mut_children_with(self);
+    if node.type_only {
+      for specifier in node.specifiers.iter_mut() {
+        if let ExportSpecifier::Named(named) = specifier {
+          named.is_type_only = false;
+        }
+      }
+    }
+  }
+
+  fn visit_mut_import_specifiers(&mut self, specifiers: &mut Vec<ImportSpecifier>) {
+    specifiers.visit_mut_children_with(self);
+    specifiers.sort_by_cached_key(get_import_specifier_sort_key);
+  }
+
+  fn visit_mut_export_specifiers(&mut self, specifiers: &mut Vec<ExportSpecifier>) {
+    specifiers.visit_mut_children_with(self);
+    specifiers.sort_by_cached_key(get_export_specifier_sort_key);
+  }
+
+  fn visit_mut_jsx_element_childs(&mut self, children: &mut Vec<JSXElementChild>) {
+    children.visit_mut_children_with(self);
+    normalize_jsx_children(children);
+  }
+
+  fn visit_mut_tagged_tpl(&mut self, node: &mut TaggedTpl) {
+    node.visit_mut_children_with(self);
+    // the external formatter may change the text of embedded languages
+    if let Some(embedded_languages) = self.embedded_languages {
+      if get_tag_path(&node.tag).is_some_and(|path| get_language_from_tag_path(embedded_languages, path).is_some()) {
+        clear_template_text(&mut node.tpl);
+      }
+    }
+  }
+
+  fn visit_mut_decorator(&mut self, node: &mut Decorator) {
+    node.visit_mut_children_with(self);
+    // the template and styles of Angular components are embedded languages
+    if self.embedded_languages.is_none() {
+      return;
+    }
+    let Expr::Call(call_expr) = &mut *node.expr else {
+      return;
+    };
+    if !matches!(&call_expr.callee, Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if ident.sym == "Component")) {
+      return;
+    }
+    let Some(Expr::Object(object_lit)) = call_expr.args.first_mut().map(|arg| &mut *arg.expr) else {
+      return;
+    };
+    for prop in object_lit.props.iter_mut() {
+      let PropOrSpread::Prop(prop) = prop else {
+        continue;
+      };
+      let Prop::KeyValue(prop) = &mut **prop else {
+        continue;
+      };
+      // identifier keys were normalized to strings when visiting the children
+      if !matches!(&prop.key, PropName::Str(key) if key.value == "template" || key.value == "styles") {
+        continue;
+      }
+      match &mut *prop.value {
+        Expr::Tpl(tpl) => clear_template_text(tpl),
+        Expr::Array(array_lit) => {
+          for element in array_lit.elems.iter_mut().flatten() {
+            if let Expr::Tpl(tpl) = &mut *element.expr {
+              clear_template_text(tpl);
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+
+  fn visit_mut_tpl(&mut self, node: &mut Tpl) {
+    node.visit_mut_children_with(self);
+    // templates with a leading block comment may have their language hinted (ex. `/* sql */`)
+    if let Some(embedded_languages) = self.embedded_languages {
+      let has_language_hint = self
+        .comments
+        .get_leading(node.range().start)
+        .and_then(|comments| comments.last())
+        .is_some_and(|comment| comment.kind == CommentKind::Block && get_language_from_comment_hint(embedded_languages, &comment.text).is_some());
+      if has_language_hint {
+        clear_template_text(node);
+      }
+    }
+  }
+}
+
+/// Gets the path of a tagged template's tag (ex. `styled.div` for `styled.div` and `styled(Button)`).
+fn get_tag_path(tag: &Expr) -> Option<String> {
+  match tag {
+    Expr::Ident(ident) => Some(ident.sym.to_string()),
+    Expr::Member(member_expr) => match &member_expr.prop {
+      MemberProp::Ident(prop) => Some(format!("{}.{}", get_tag_path(&member_expr.obj)?, prop.sym)),
+      _ => None,
+    },
+    Expr::Call(call_expr) => match &call_expr.callee {
+      Callee::Expr(callee) => get_tag_path(callee),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+fn clear_template_text(tpl: &mut Tpl) {
+  for quasi in tpl.quasis.iter_mut() {
+    quasi.raw = Default::default();
+    quasi.cooked = None;
+  }
+}
+
+fn ident_to_str(sym: deno_ast::swc::atoms::Atom) -> Str {
+  Str {
+    span: DUMMY_SP,
+    value: sym,
+    raw: None,
+  }
+}
+
+fn unwrap_single_stmt_block(stmt: &mut Box<Stmt>) {
+  if let Stmt::Block(block) = &mut **stmt {
+    if block.stmts.len() == 1 && !matches!(block.stmts[0], Stmt::Decl(_)) {
+      **stmt = block.stmts.pop().unwrap();
+    }
+  }
+}
+
+fn sort_consecutive(items: &mut [ModuleItem], get_key: impl Fn(&ModuleItem) -> Option<String>) {
+  let mut start = 0;
+  while start < items.len() {
+    let mut end = start;
+    while end < items.len() && get_key(&items[end]).is_some() {
+      end += 1;
+    }
+    if end > start {
+      items[start..end].sort_by_cached_key(|item| get_key(item).unwrap());
+      start = end;
+    } else {
+      start += 1;
+    }
+  }
+}
+
+fn get_import_sort_key(item: &ModuleItem) -> Option<String> {
+  match item {
+    ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(format!(
+      "{}|{}|{}",
+      import.src.value,
+      import.type_only,
+      import.specifiers.iter().map(get_import_specifier_sort_key).collect::<Vec<_>>().join(",")
+    )),
+    _ => None,
+  }
+}
+
+fn get_re_export_sort_key(item: &ModuleItem) -> Option<String> {
+  match item {
+    ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+      src: Some(src),
+      specifiers,
+      type_only,
+      ..
+    })) => Some(format!(
+      "{}|{}|{}",
+      src.value,
+      type_only,
+      specifiers.iter().map(get_export_specifier_sort_key).collect::<Vec<_>>().join(",")
+    )),
+    ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export_all)) => Some(format!("{}|{}|*", export_all.src.value, export_all.type_only)),
+    _ => None,
+  }
+}
+
+fn get_import_specifier_sort_key(specifier: &ImportSpecifier) -> String {
+  match specifier {
+    ImportSpecifier::Named(named) => format!(
+      "{}|{}|{}",
+      named.local.sym,
+      named.imported.as_ref().map(get_module_export_name).unwrap_or_default(),
+      named.is_type_only
+    ),
+    ImportSpecifier::Default(default) => format!("default|{}", default.local.sym),
+    ImportSpecifier::Namespace(namespace) => format!("*|{}", namespace.local.sym),
+  }
+}
+
+fn get_export_specifier_sort_key(specifier: &ExportSpecifier) -> String {
+  match specifier {
+    ExportSpecifier::Named(named) => format!(
+      "{}|{}|{}",
+      get_module_export_name(&named.orig),
+      named.exported.as_ref().map(get_module_export_name).unwrap_or_default(),
+      named.is_type_only
+    ),
+    ExportSpecifier::Default(default) => format!("default|{}", default.exported.sym),
+    ExportSpecifier::Namespace(namespace) => format!("*|{}", get_module_export_name(&namespace.name)),
+  }
+}
+
+fn get_module_export_name(name: &ModuleExportName) -> String {
+  match name {
+    ModuleExportName::Ident(ident) => ident.sym.to_string(),
+    ModuleExportName::Str(str) => str.value.to_string(),
+  }
+}
+
+/// Merges the JSX text and space expressions (ex. `{" "}`) into the text they represent
+/// since the formatter may move whitespace between them. Whitespace is collapsed
+/// and removed at the start and end of the children like the formatter does.
+fn normalize_jsx_children(children: &mut Vec<JSXElementChild>) {
+  let mut new_children = Vec::with_capacity(children.len());
+  let mut text = String::new();
+  for child in children.drain(..) {
+    match child {
+      JSXElementChild::JSXText(jsx_text) => text.push_str(&clean_jsx_text(&jsx_text.value)),
+      JSXElementChild::JSXExprContainer(JSXExprContainer { expr: JSXExpr::Expr(expr), .. }) if matches!(&*expr, Expr::Lit(Lit::Str(str)) if !str.value.is_empty() && str.value.chars().all(|c| c == ' ')) => {
+        text.push(' ')
+      }
+      child => {
+        flush_jsx_text(&mut new_children, &mut text);
+        new_children.push(child);
+      }
+    }
+  }
+  flush_jsx_text(&mut new_children, &mut text);
+  if let Some(JSXElementChild::JSXText(jsx_text)) = new_children.first_mut() {
+    set_jsx_text(jsx_text, jsx_text.value.trim_start().to_string());
+  }
+  if let Some(JSXElementChild::JSXText(jsx_text)) = new_children.last_mut() {
+    set_jsx_text(jsx_text, jsx_text.value.trim_end().to_string());
+  }
+  new_children.retain(|child| !matches!(child, JSXElementChild::JSXText(jsx_text) if jsx_text.value.is_empty()));
+  *children = new_children;
+}
+
+fn set_jsx_text(jsx_text: &mut JSXText, text: String) {
+  jsx_text.value = text.as_str().into();
+  jsx_text.raw = text.into();
+}
+
+fn flush_jsx_text(children: &mut Vec<JSXElementChild>, text: &mut String) {
+  if !text.is_empty() {
+    let text = collapse_whitespace(&std::mem::take(text));
+    children.push(JSXElementChild::JSXText(JSXText {
+      span: DUMMY_SP,
+      value: text.as_str().into(),
+      raw: text.into(),
+    }));
+  }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  let mut result = String::with_capacity(text.len());
+  for c in text.chars() {
+    if c.is_whitespace() {
+      if !result.ends_with(' ') {
+        result.push(' ');
+      }
+    } else {
+      result.push(c);
+    }
+  }
+  result
+}
+
+/// Gets the text JSX text represents, which removes the whitespace
+/// surrounding new lines and lines that only have whitespace.
+fn clean_jsx_text(text: &str) -> String {
+  if !text.contains('\n') {
+    return text.to_string();
+  }
+  let lines = text.split('\n').collect::<Vec<_>>();
+  let last_index = lines.len() - 1;
+  let mut result = String::new();
+  for (i, line) in lines.into_iter().enumerate() {
+    let mut line = line;
+    if i > 0 {
+      line = line.trim_start_matches([' ', '\t', '\r']);
+    }
+    if i < last_index {
+      line = line.trim_end_matches([' ', '\t', '\r']);
+    }
+    if !line.is_empty() {
+      if !result.is_empty() {
+        result.push(' ');
+      }
+      result.push_str(line);
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+
+  use super::*;
+  use crate::swc::parse_swc_ast;
+
+  fn check(original: &str, formatted: &str) -> Result<()> {
+    let parsed_source = parse_swc_ast(&PathBuf::from("test.tsx"), None, original.into()).unwrap();
+    ensure_ast_equivalence(&parsed_source, formatted, None)
+  }
+
+  #[test]
+  fn allows_formatter_changes() {
+    check("const a = (1 + 2) * (b);", "const a = (1 + 2) * b;\n").unwrap();
+    check("type A = (string);", "type A = string;\n").unwrap();
+    check("if (a) { b() }", "if (a) {\n  b();\n}\n").unwrap();
+    check("if (a) b();", "if (a) {\n  b();\n}\n").unwrap();
+    check(
+      "import { b, a } from 'b';\nimport c from 'a';",
+      "import c from \"a\";\nimport { a, b } from \"b\";\n",
+    )
+    .unwrap();
+    check(
+      "const a = <div>  test\n  <b /> </div>;",
+      "const a = (\n  <div>\n    test\n    <b />\n  </div>\n);\n",
+    )
+    .unwrap();
+    check(
+      "const a = <div>a <b /> c</div>;",
+      "const a = (\n  <div>\n    a <b />{\" \"}\n    c\n  </div>\n);\n",
+    )
+    .unwrap();
+  }
+
+  #[test]
+  fn errors_on_changed_syntax_tree() {
+    let err = check("let a = 1;\nconst b = (1 + 2) * 3;", "let a = 1;\nconst b = 1 + 2 * 3;\n").unwrap_err();
+    match err.downcast_ref::<FormatError>().unwrap() {
+      FormatError::Internal {
+        location: Some(location),
+        message,
+      } => {
+        assert_eq!(location.line_number, 2);
+        assert_eq!(location.column_number, 1);
+        assert_eq!(message, "Formatting changed the syntax tree of the code. This is a bug in the formatter.");
+      }
+      _ => unreachable!(),
+    }
+    assert!(check("let a = 1;", "let a = 1;;\nlet b = 2;\n").is_err());
+    assert!(check(
+      "import a from 'a';\nlet a = 1;\nimport b from 'b';",
+      "import b from \"b\";\nlet a = 1;\nimport a from \"a\";\n"
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn ignores_only_embedded_language_text() {
+    let languages = crate::configuration::ConfigurationBuilder::new().build().tagged_templates_languages;
+    let check = |original: &str, formatted: &str| {
+      let parsed_source = parse_swc_ast(&PathBuf::from("test.ts"), None, original.into()).unwrap();
+      ensure_ast_equivalence(&parsed_source, formatted, Some(&languages))
+    };
+    check("const a = /* sql */ `SELECT  *`;", "const a = /* sql */ `SELECT *`;\n").unwrap();
+    check("const a = /* CSS */ `a{}`;", "const a = /* CSS */ `a {}`;\n").unwrap();
+    check("const a = css`a{}`;", "const a = css`a {}`;\n").unwrap();
+    check("const a = styled.div`a{}`;", "const a = styled.div`a {}`;\n").unwrap();
+    // the tag is not an embedded language, so the text must not change
+    assert!(check("const a = String.raw`a{}`;", "const a = String.raw`a {}`;\n").is_err());
+    assert!(check("const a = foo`a{}`;", "const a = foo`a {}`;\n").is_err());
+    // the comment is not a language hint, so the text must not change
+    assert!(check("const a = /* eslint-disable */ `a${b}`;", "const a = /* eslint-disable */ `b${b}`;\n").is_err());
+    assert!(check("const a = /* sql */ `a`;", "const a = /* sql */ `b`;\n").is_ok());
+    let parsed_source = parse_swc_ast(&PathBuf::from("test.ts"), None, "const a = /* sql */ `a`;".into()).unwrap();
+    assert!(ensure_ast_equivalence(&parsed_source, "const a = /* sql */ `b`;\n", None).is_err());
+  }
+}
@@ -0,0 +1,4 @@
+/// Runs the formatting language server over stdin and stdout.
+fn main() -> anyhow::Result<()> {
+  dprint_plugin_typescript::run_lsp_server(std::io::stdin().lock(), std::io::stdout().lock())
+}
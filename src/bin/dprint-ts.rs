@@ -0,0 +1,361 @@
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::GlobalConfiguration;
+use dprint_plugin_typescript::configuration::resolve_config;
+use dprint_plugin_typescript::configuration::resolve_config_file_text;
+use dprint_plugin_typescript::format_text;
+use dprint_plugin_typescript::FormatTextOptions;
+use dprint_plugin_typescript::Formatter;
+
+const HELP_TEXT: &str = "Formats TypeScript and JavaScript files.
+
+Usage: dprint-ts [OPTIONS] [PATHS]...
+
+Directories are searched for files with a TypeScript or JavaScript extension,
+skipping hidden directories and node_modules.
+
+Options:
+      --check                   Lists the files that aren't formatted
+      --write                   Formats the files in place
+      --diff                    Outputs a diff of the changes for the files that aren't formatted
+      --stdin                   Formats the text from stdin and outputs it to stdout
+      --stdin-filepath <PATH>   Path used to determine how to parse the stdin text [default: stdin.ts]
+  -c, --config <PATH>           JSON or JSONC configuration file (ex. a dprint.json file or the \"typescript\" configuration)
+  -h, --help                    Prints help
+  -V, --version                 Prints the version
+
+Exit codes:
+  0  Success
+  1  Files weren't formatted (--check, --diff and --stdin with --check)
+  2  An error occurred";
+
+const FILE_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts", "gts", "gjs"];
+
+const EXIT_CODE_NOT_FORMATTED: u8 = 1;
+const EXIT_CODE_ERROR: u8 = 2;
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+  check: bool,
+  write: bool,
+  diff: bool,
+  stdin: bool,
+  stdin_file_path: Option<PathBuf>,
+  config_path: Option<PathBuf>,
+  paths: Vec<PathBuf>,
+  help: bool,
+  version: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args> {
+  let mut parsed = Args::default();
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    let (name, inline_value) = match arg.split_once('=') {
+      Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+      _ => (arg.clone(), None),
+    };
+    let mut take_value = |name: &str| match inline_value.clone().or_else(|| args.next()) {
+      Some(value) => Ok(PathBuf::from(value)),
+      None => bail!("Expected a value for {}.", name),
+    };
+    match name.as_str() {
+      "--check" => parsed.check = true,
+      "--write" => parsed.write = true,
+      "--diff" => parsed.diff = true,
+      "--stdin" => parsed.stdin = true,
+      "--stdin-filepath" => parsed.stdin_file_path = Some(take_value(&name)?),
+      "-c" | "--config" => parsed.config_path = Some(take_value(&name)?),
+      "-h" | "--help" => parsed.help = true,
+      "-V" | "--version" => parsed.version = true,
+      "--" => parsed.paths.extend(args.by_ref().map(PathBuf::from)),
+      _ if name.starts_with('-') && name != "-" => bail!("Unknown option: {}", name),
+      _ => parsed.paths.push(PathBuf::from(arg)),
+    }
+  }
+
+  if parsed.help || parsed.version {
+    return Ok(parsed);
+  }
+  if parsed.write && (parsed.check || parsed.diff) {
+    bail!("--write cannot be used with --check or --diff.");
+  }
+  if parsed.stdin {
+    if parsed.write {
+      bail!("--write cannot be used with --stdin.");
+    }
+    if !parsed.paths.is_empty() {
+      bail!("Paths cannot be provided with --stdin. Use --stdin-filepath to specify the path of the text.");
+    }
+  } else {
+    if parsed.stdin_file_path.is_some() {
+      bail!("--stdin-filepath can only be used with --stdin.");
+    }
+    if parsed.paths.is_empty() {
+      bail!("Expected paths to format or --stdin. Run with --help for usage.");
+    }
+    if !parsed.check && !parsed.write && !parsed.diff {
+      bail!("Expected --check, --write or --diff when formatting files.");
+    }
+  }
+  Ok(parsed)
+}
+
+/// Gets the files to format, searching the provided directories.
+fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+  fn visit_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+      .with_context(|| format!("Failed reading directory {}", dir.display()))?
+      .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+      let path = entry.path();
+      let file_name = entry.file_name();
+      let file_name = file_name.to_string_lossy();
+      if entry.file_type()?.is_dir() {
+        if !file_name.starts_with('.') && file_name != "node_modules" {
+          visit_dir(&path, files)?;
+        }
+      } else if has_matching_extension(&path) {
+        files.push(path);
+      }
+    }
+    Ok(())
+  }
+
+  let mut files = Vec::new();
+  for path in paths {
+    if path.is_dir() {
+      visit_dir(path, &mut files)?;
+    } else if path.is_file() {
+      files.push(path.clone());
+    } else {
+      bail!("Could not find {}", path.display());
+    }
+  }
+  Ok(files)
+}
+
+fn has_matching_extension(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn get_diff(path: &Path, old_text: &str, new_text: &str) -> String {
+  let path = path.to_string_lossy();
+  similar::TextDiff::from_lines(old_text, new_text)
+    .unified_diff()
+    .context_radius(3)
+    .header(&path, &path)
+    .to_string()
+}
+
+fn run(args: Args) -> Result<u8> {
+  let config = match &args.config_path {
+    Some(config_path) => {
+      let text = std::fs::read_to_string(config_path).with_context(|| format!("Failed reading config file {}", config_path.display()))?;
+      resolve_config_file_text(&text).with_context(|| format!("Failed resolving config file {}", config_path.display()))?
+    }
+    None => resolve_config(ConfigKeyMap::new(), &GlobalConfiguration::default()).config,
+  };
+
+  if args.stdin {
+    let file_path = args.stdin_file_path.unwrap_or_else(|| PathBuf::from("stdin.ts"));
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    let formatted_text = format_text(FormatTextOptions {
+      path: &file_path,
+      extension: None,
+      text: text.clone(),
+      config: &config,
+      external_formatter: None,
+    })
+    .with_context(|| format!("Failed formatting {}", file_path.display()))?;
+    let mut stdout = std::io::stdout().lock();
+    return match formatted_text {
+      Some(formatted_text) if args.check || args.diff => {
+        if args.diff {
+          stdout.write_all(get_diff(&file_path, &text, &formatted_text).as_bytes())?;
+        }
+        Ok(EXIT_CODE_NOT_FORMATTED)
+      }
+      Some(formatted_text) => {
+        stdout.write_all(formatted_text.as_bytes())?;
+        Ok(0)
+      }
+      None => {
+        if !args.check && !args.diff {
+          stdout.write_all(text.as_bytes())?;
+        }
+        Ok(0)
+      }
+    };
+  }
+
+  let files = collect_files(&args.paths)?
+    .into_iter()
+    .map(|path| {
+      let text = std::fs::read_to_string(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+      Ok((path, text))
+    })
+    .collect::<Result<Vec<_>>>()?;
+  let original_texts = files.iter().map(|(_, text)| text.clone()).collect::<Vec<_>>();
+  let paths = files.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+  let results = Formatter::new(config).format_many(files);
+
+  let mut stdout = std::io::stdout().lock();
+  let mut not_formatted_count = 0;
+  let mut error_count = 0;
+  for ((path, original_text), result) in paths.iter().zip(original_texts).zip(results) {
+    match result {
+      Ok(Some(formatted_text)) => {
+        not_formatted_count += 1;
+        if args.write {
+          std::fs::write(path, formatted_text).with_context(|| format!("Failed writing {}", path.display()))?;
+        } else if args.diff {
+          stdout.write_all(get_diff(path, &original_text, &formatted_text).as_bytes())?;
+        } else {
+          writeln!(stdout, "{}", path.display())?;
+        }
+      }
+      Ok(None) => {}
+      Err(err) => {
+        error_count += 1;
+        eprintln!("Error formatting {}: {:#}", path.display(), err);
+      }
+    }
+  }
+
+  if args.write {
+    if not_formatted_count > 0 {
+      eprintln!("Formatted {} {}.", not_formatted_count, if not_formatted_count == 1 { "file" } else { "files" });
+    }
+  } else if not_formatted_count > 0 {
+    eprintln!(
+      "Found {} not formatted {}.",
+      not_formatted_count,
+      if not_formatted_count == 1 { "file" } else { "files" }
+    );
+  }
+
+  Ok(if error_count > 0 {
+    EXIT_CODE_ERROR
+  } else if not_formatted_count > 0 && !args.write {
+    EXIT_CODE_NOT_FORMATTED
+  } else {
+    0
+  })
+}
+
+fn main() -> ExitCode {
+  let args = match parse_args(std::env::args().skip(1)) {
+    Ok(args) => args,
+    Err(err) => {
+      eprintln!("{}", err);
+      return ExitCode::from(EXIT_CODE_ERROR);
+    }
+  };
+  if args.help {
+    println!("{}", HELP_TEXT);
+    return ExitCode::SUCCESS;
+  }
+  if args.version {
+    println!("dprint-ts {}", env!("CARGO_PKG_VERSION"));
+    return ExitCode::SUCCESS;
+  }
+
+  match run(args) {
+    Ok(code) => ExitCode::from(code),
+    Err(err) => {
+      eprintln!("{:#}", err);
+      ExitCode::from(EXIT_CODE_ERROR)
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Args> {
+    parse_args(args.iter().map(|arg| arg.to_string()))
+  }
+
+  #[test]
+  fn parses_args() {
+    assert_eq!(
+      parse(&["--check", "--config=dprint.json", "src", "file.ts"]).unwrap(),
+      Args {
+        check: true,
+        config_path: Some(PathBuf::from("dprint.json")),
+        paths: vec![PathBuf::from("src"), PathBuf::from("file.ts")],
+        ..Default::default()
+      }
+    );
+    assert_eq!(
+      parse(&["--stdin", "--stdin-filepath", "file.tsx", "--diff"]).unwrap(),
+      Args {
+        stdin: true,
+        diff: true,
+        stdin_file_path: Some(PathBuf::from("file.tsx")),
+        ..Default::default()
+      }
+    );
+    assert_eq!(parse(&["--write", "--", "--file.ts"]).unwrap().paths, vec![PathBuf::from("--file.ts")]);
+    assert_eq!(
+      parse(&["src"]).unwrap_err().to_string(),
+      "Expected --check, --write or --diff when formatting files."
+    );
+    assert_eq!(
+      parse(&["--write", "--check", "src"]).unwrap_err().to_string(),
+      "--write cannot be used with --check or --diff."
+    );
+    assert_eq!(
+      parse(&["--stdin", "file.ts"]).unwrap_err().to_string(),
+      "Paths cannot be provided with --stdin. Use --stdin-filepath to specify the path of the text."
+    );
+    assert_eq!(parse(&["--config"]).unwrap_err().to_string(), "Expected a value for --config.");
+    assert_eq!(parse(&["--other"]).unwrap_err().to_string(), "Unknown option: --other");
+  }
+
+  #[test]
+  fn reads_config_with_comments() {
+    let dir_path = std::env::temp_dir().join(format!("dprint-ts-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir_path).unwrap();
+    let config_path = dir_path.join("dprint.jsonc");
+    std::fs::write(
+      &config_path,
+      "{\n  // the plugin configuration\n  \"typescript\": {\n    \"indentWidth\": 4,\n  },\n}\n",
+    )
+    .unwrap();
+    let file_path = dir_path.join("file.ts");
+    std::fs::write(&file_path, "if (a) {\n    b;\n}\n").unwrap();
+    let code = run(Args {
+      check: true,
+      config_path: Some(config_path),
+      paths: vec![file_path],
+      ..Default::default()
+    })
+    .unwrap();
+    std::fs::remove_dir_all(&dir_path).unwrap();
+    assert_eq!(code, 0);
+  }
+
+  #[test]
+  fn gets_diff() {
+    assert_eq!(
+      get_diff(Path::new("file.ts"), "const  a = 1;\nconst b = 2;\n", "const a = 1;\nconst b = 2;\n"),
+      "--- file.ts\n+++ file.ts\n@@ -1,2 +1,2 @@\n-const  a = 1;\n+const a = 1;\n const b = 2;\n"
+    );
+  }
+}
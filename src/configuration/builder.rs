@@ -584,6 +584,44 @@ impl ConfigurationBuilder {
     self.insert("ignoreFileCommentText", value.into())
   }
 
+  /* verification */
+
+  /// Whether to parse the formatted text and error when its syntax tree
+  /// differs from the original text's syntax tree, instead of outputting it.
+  ///
+  /// This is a safety check against bugs in the formatter and slows down formatting.
+  ///
+  /// Default: `false`
+  pub fn verify_ast_equivalence(&mut self, value: bool) -> &mut Self {
+    self.insert("verifyAstEquivalence", value.into())
+  }
+
+  /// Whether to format the formatted text a second time and error when
+  /// that changes it, instead of outputting text that isn't stable.
+  ///
+  /// The error's location is the first line that differs in the formatted text.
+  ///
+  /// Default: `false`
+  pub fn verify_idempotency(&mut self, value: bool) -> &mut Self {
+    self.insert("verifyIdempotency", value.into())
+  }
+
+  /* embedded languages */
+
+  /// Maps tag names to the language of their tagged templates. The language is
+  /// provided to the external formatter, which leaves languages it doesn't know alone.
+  ///
+  /// Tag names are identifiers or member paths (ex. `dbQuery` or `styled.div`). A member
+  /// path or call also matches the start of its path, so `styled` matches `styled.div` and `styled(Button)`.
+  ///
+  /// These are added to the default tag names. In a configuration file, map a tag name to `null` to remove a default.
+  ///
+  /// Default: `{ "css": "css", "gql": "graphql", "graphql": "graphql", "html": "html", "sql": "sql", "styled": "css" }`
+  pub fn tagged_templates_languages<'a>(&mut self, value: impl IntoIterator<Item = (&'a str, &'a str)>) -> &mut Self {
+    let languages = value.into_iter().map(|(tag, language)| (tag.to_string(), language.into())).collect();
+    self.insert("taggedTemplates.languages", ConfigKeyValue::Object(languages))
+  }
+
   /* brace position */
 
   pub fn arrow_function_brace_position(&mut self, value: BracePosition) -> &mut Self {
@@ -1136,6 +1174,11 @@ mod tests {
       /* ignore comments */
       .ignore_node_comment_text("ignore")
       .ignore_file_comment_text("ignore-file")
+      /* verification */
+      .verify_ast_equivalence(true)
+      .verify_idempotency(true)
+      /* embedded languages */
+      .tagged_templates_languages([("dbQuery", "sql")])
       /* brace position*/
       .arrow_function_brace_position(BracePosition::NextLine)
       .class_declaration_brace_position(BracePosition::NextLine)
@@ -1297,7 +1340,7 @@ mod tests {
       .while_statement_space_around(true);
 
     let inner_config = config.get_inner_config();
-    assert_eq!(inner_config.len(), 182);
+    assert_eq!(inner_config.len(), 185);
     let diagnostics = resolve_config(inner_config, &Default::default()).diagnostics;
     assert_eq!(diagnostics.len(), 0);
   }
@@ -0,0 +1,76 @@
+use anyhow::bail;
+use anyhow::Result;
+use dprint_core::configuration::*;
+
+use super::resolve_config::resolve_config;
+use super::types::Configuration;
+
+/// Resolves the configuration from the text of a JSON configuration file, which may
+/// have comments and trailing commas like dprint configuration files.
+///
+/// See `resolve_config_file_map`.
+pub fn resolve_config_file_text(text: &str) -> Result<Configuration> {
+  let config_map = match jsonc_parser::parse_to_serde_value(text, &Default::default())? {
+    Some(value) => serde_json::from_value(value)?,
+    None => ConfigKeyMap::new(),
+  };
+  resolve_config_file_map(config_map)
+}
+
+/// Resolves the configuration of a configuration file, erroring for any diagnostics.
+///
+/// The file may be a dprint configuration file (it has a "typescript" or "plugins" property),
+/// where the global configuration is at the top level and the plugin configuration is in
+/// the "typescript" property, or only the plugin configuration.
+pub fn resolve_config_file_map(mut config_map: ConfigKeyMap) -> Result<Configuration> {
+  let is_dprint_config_file = config_map.contains_key("typescript") || config_map.contains_key("plugins");
+  let (plugin_config, global_config) = if is_dprint_config_file {
+    let plugin_config = match config_map.shift_remove("typescript") {
+      Some(ConfigKeyValue::Object(plugin_config)) => plugin_config,
+      Some(_) => bail!("Expected the \"typescript\" property to be an object."),
+      None => ConfigKeyMap::new(),
+    };
+    // the other properties are for the dprint CLI or other plugins
+    let global_config_result = resolve_global_config(&mut config_map);
+    ensure_no_diagnostics(global_config_result.diagnostics)?;
+    (plugin_config, global_config_result.config)
+  } else {
+    (config_map, GlobalConfiguration::default())
+  };
+  let config_result = resolve_config(plugin_config, &global_config);
+  ensure_no_diagnostics(config_result.diagnostics)?;
+  Ok(config_result.config)
+}
+
+fn ensure_no_diagnostics(diagnostics: Vec<ConfigurationDiagnostic>) -> Result<()> {
+  if diagnostics.is_empty() {
+    Ok(())
+  } else {
+    let messages = diagnostics.iter().map(|d| format!("  {}", d)).collect::<Vec<_>>();
+    bail!("Invalid configuration:\n{}", messages.join("\n"))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn resolves_config_file() {
+    let config = resolve_config_file_text(r#"{ "lineWidth": 40, "plugins": [], "typescript": { "indentWidth": 4 } }"#).unwrap();
+    assert_eq!(config.line_width, 40);
+    assert_eq!(config.indent_width, 4);
+    let config = resolve_config_file_text(r#"{ "indentWidth": 8, "excludes": [], "plugins": [] }"#).unwrap();
+    assert_eq!(config.indent_width, 8);
+    let config = resolve_config_file_text(r#"{ "lineWidth": 60, "semiColons": "asi" }"#).unwrap();
+    assert_eq!(config.line_width, 60);
+    let config = resolve_config_file_text("{\n  // comment\n  \"typescript\": {\n    /* comment */ \"indentWidth\": 4,\n  },\n}\n").unwrap();
+    assert_eq!(config.indent_width, 4);
+    assert_eq!(resolve_config_file_text("").unwrap().indent_width, 2);
+    assert!(resolve_config_file_text("{ \"indentWidth\": }").is_err());
+    assert_eq!(
+      resolve_config_file_text(r#"{ "typescript": { "unknown": true } }"#).err().unwrap().to_string(),
+      "Invalid configuration:\n  Unknown property in configuration (unknown)"
+    );
+  }
+}
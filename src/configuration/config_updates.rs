@@ -0,0 +1,107 @@
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::plugins::ConfigChange;
+use dprint_core::plugins::ConfigChangeKind;
+
+use super::resolve_config::RENAMED_CONFIG_PROPERTIES;
+
+/// The version "jsx.multiLineParens" changed from a boolean to "never", "prefer" or "always".
+const JSX_MULTI_LINE_PARENS_VERSION: &str = "0.80.0";
+
+/// Gets the changes that migrate configuration written for previous versions
+/// of the plugin (ex. renamed properties) for `dprint config update`.
+///
+/// Only the migrations for the versions after the `old_version` of the plugin are
+/// done. All are done when it's unknown (ex. for dprint versions before 0.47).
+/// The paths of the changes are relative to the plugin's configuration.
+pub fn get_config_updates(old_version: Option<&str>, config: &ConfigKeyMap) -> Vec<ConfigChange> {
+  let old_version = old_version.and_then(parse_version);
+  let is_before = |version: &str| match (old_version, parse_version(version)) {
+    (Some(old_version), Some(version)) => old_version < version,
+    _ => true,
+  };
+  let mut changes = Vec::new();
+
+  for (old_key, new_key, version) in RENAMED_CONFIG_PROPERTIES {
+    if !is_before(version) {
+      continue;
+    }
+    let Some(value) = config.get(old_key) else {
+      continue;
+    };
+    // when both are set, the new property takes precedence
+    let has_new_key = config.contains_key(new_key) || changes.iter().any(|change: &ConfigChange| change.path == [new_key.to_string().into()]);
+    if !has_new_key {
+      changes.push(ConfigChange {
+        path: vec![new_key.to_string().into()],
+        kind: ConfigChangeKind::Add(value.clone()),
+      });
+    }
+    changes.push(ConfigChange {
+      path: vec![old_key.to_string().into()],
+      kind: ConfigChangeKind::Remove,
+    });
+  }
+
+  if is_before(JSX_MULTI_LINE_PARENS_VERSION) {
+    if let Some(ConfigKeyValue::Bool(value)) = config.get("jsx.multiLineParens") {
+      changes.push(ConfigChange {
+        path: vec!["jsx.multiLineParens".to_string().into()],
+        kind: ConfigChangeKind::Set(if *value { "prefer" } else { "never" }.into()),
+      });
+    }
+  }
+
+  changes
+}
+
+/// Parses the major, minor and patch numbers of a version (ex. `0.80.0` or `0.80.0-beta`).
+fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+  let mut parts = text.trim().split(['-', '+']).next()?.split('.').map(|part| part.parse::<u64>().ok());
+  let version = (parts.next()??, parts.next()??, parts.next()??);
+  parts.next().is_none().then_some(version)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn gets_config_updates() {
+    let mut config = ConfigKeyMap::new();
+    config.insert("jsxElement.spaceBeforeSelfClosingTagSlash".to_string(), true.into());
+    config.insert("jsx.spaceBeforeSelfClosingTagSlash".to_string(), false.into());
+    config.insert("jsx.multiLineParens".to_string(), false.into());
+    config.insert("lineWidth".to_string(), 80.into());
+    let get_changes = |old_version: Option<&str>| {
+      get_config_updates(old_version, &config)
+        .into_iter()
+        .map(|change| serde_json::to_string(&change).unwrap())
+        .collect::<Vec<_>>()
+    };
+    let all_changes = vec![
+      r#"{"path":["jsxSelfClosingElement.spaceBeforeSlash"],"kind":"Add","value":true}"#,
+      r#"{"path":["jsxElement.spaceBeforeSelfClosingTagSlash"],"kind":"Remove"}"#,
+      r#"{"path":["jsx.spaceBeforeSelfClosingTagSlash"],"kind":"Remove"}"#,
+      r#"{"path":["jsx.multiLineParens"],"kind":"Set","value":"never"}"#,
+    ];
+    assert_eq!(get_changes(None), all_changes);
+    assert_eq!(get_changes(Some("0.60.2")), all_changes);
+    assert_eq!(get_changes(Some("not a version")), all_changes);
+    assert_eq!(
+      get_changes(Some("0.61.0")),
+      vec![r#"{"path":["jsx.multiLineParens"],"kind":"Set","value":"never"}"#]
+    );
+    assert!(get_changes(Some("0.80.0")).is_empty());
+    assert!(get_changes(Some("0.95.4")).is_empty());
+    assert!(get_config_updates(None, &ConfigKeyMap::new()).is_empty());
+  }
+
+  #[test]
+  fn parses_versions() {
+    assert_eq!(parse_version("0.80.0"), Some((0, 80, 0)));
+    assert_eq!(parse_version("1.2.3-beta.1"), Some((1, 2, 3)));
+    assert_eq!(parse_version("1.2"), None);
+    assert_eq!(parse_version("1.2.3.4"), None);
+  }
+}
@@ -1,7 +1,15 @@
 mod builder;
+#[cfg(any(feature = "cli", feature = "lsp", feature = "ffi"))]
+mod config_file;
+#[cfg(any(feature = "wasm", feature = "process"))]
+mod config_updates;
 mod resolve_config;
 mod types;
 
 pub use builder::*;
+#[cfg(any(feature = "cli", feature = "lsp", feature = "ffi"))]
+pub use config_file::*;
+#[cfg(any(feature = "wasm", feature = "process"))]
+pub use config_updates::*;
 pub use resolve_config::*;
 pub use types::*;
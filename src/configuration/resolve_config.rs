@@ -1,7 +1,15 @@
+use std::collections::BTreeMap;
+
 use super::builder::*;
 use super::types::*;
 use dprint_core::configuration::*;
 
+/// Properties that were renamed in previous versions (old key, new key, version of the rename).
+pub(crate) const RENAMED_CONFIG_PROPERTIES: [(&str, &str, &str); 2] = [
+  ("jsxElement.spaceBeforeSelfClosingTagSlash", "jsxSelfClosingElement.spaceBeforeSlash", "0.61.0"),
+  ("jsx.spaceBeforeSelfClosingTagSlash", "jsxSelfClosingElement.spaceBeforeSlash", "0.61.0"),
+];
+
 /// Resolves configuration from a collection of key value strings.
 ///
 /// # Example
@@ -32,19 +40,10 @@ pub fn resolve_config(config: ConfigKeyMap, global_config: &GlobalConfiguration)
     fill_deno_config(&mut config);
   }
 
-  // show diagnostics for renaming this property
-  handle_renamed_config_property(
-    &mut config,
-    "jsxElement.spaceBeforeSelfClosingTagSlash",
-    "jsxSelfClosingElement.spaceBeforeSlash",
-    &mut diagnostics,
-  );
-  handle_renamed_config_property(
-    &mut config,
-    "jsx.spaceBeforeSelfClosingTagSlash",
-    "jsxSelfClosingElement.spaceBeforeSlash",
-    &mut diagnostics,
-  );
+  // show diagnostics for renamed properties
+  for (old_key, new_key, _) in RENAMED_CONFIG_PROPERTIES {
+    handle_renamed_config_property(&mut config, old_key, new_key, &mut diagnostics);
+  }
 
   let semi_colons = get_value(&mut config, "semiColons", SemiColons::Prefer, &mut diagnostics);
   let brace_position = get_value(&mut config, "bracePosition", BracePosition::SameLineUnlessHanging, &mut diagnostics);
@@ -135,6 +134,11 @@ pub fn resolve_config(config: ConfigKeyMap, global_config: &GlobalConfiguration)
     /* ignore comments */
     ignore_node_comment_text: get_value(&mut config, "ignoreNodeCommentText", String::from("dprint-ignore"), &mut diagnostics),
     ignore_file_comment_text: get_value(&mut config, "ignoreFileCommentText", String::from("dprint-ignore-file"), &mut diagnostics),
+    /* verification */
+    verify_ast_equivalence: get_value(&mut config, "verifyAstEquivalence", false, &mut diagnostics),
+    verify_idempotency: get_value(&mut config, "verifyIdempotency", false, &mut diagnostics),
+    /* embedded languages */
+    tagged_templates_languages: get_tagged_templates_languages(&mut config, &mut diagnostics),
     /* brace position */
     arrow_function_brace_position: get_value(&mut config, "arrowFunction.bracePosition", brace_position, &mut diagnostics),
     class_declaration_brace_position: get_value(&mut config, "classDeclaration.bracePosition", brace_position, &mut diagnostics),
@@ -349,6 +353,42 @@ pub fn resolve_config(config: ConfigKeyMap, global_config: &GlobalConfiguration)
       }
     }
   }
+
+  fn get_tagged_templates_languages(config: &mut ConfigKeyMap, diagnostics: &mut Vec<ConfigurationDiagnostic>) -> BTreeMap<String, String> {
+    let property_name = "taggedTemplates.languages";
+    let mut languages = BTreeMap::from([
+      ("css".to_string(), "css".to_string()),
+      ("gql".to_string(), "graphql".to_string()),
+      ("graphql".to_string(), "graphql".to_string()),
+      ("html".to_string(), "html".to_string()),
+      ("sql".to_string(), "sql".to_string()),
+      ("styled".to_string(), "css".to_string()),
+    ]);
+    match config.shift_remove(property_name) {
+      None => {}
+      Some(ConfigKeyValue::Object(values)) => {
+        for (tag, value) in values {
+          match value {
+            ConfigKeyValue::String(language) => {
+              languages.insert(tag, language);
+            }
+            ConfigKeyValue::Null => {
+              languages.remove(&tag);
+            }
+            _ => diagnostics.push(ConfigurationDiagnostic {
+              property_name: property_name.to_string(),
+              message: format!("Expected the language of '{}' to be a string or null.", tag),
+            }),
+          }
+        }
+      }
+      Some(_) => diagnostics.push(ConfigurationDiagnostic {
+        property_name: property_name.to_string(),
+        message: "Expected an object that maps tag names to languages.".to_string(),
+      }),
+    }
+    languages
+  }
 }
 
 #[cfg(test)]
@@ -410,4 +450,29 @@ mod tests {
     assert_eq!(result.config.line_width, expected_config.line_width);
     assert_eq!(result.diagnostics.len(), 0);
   }
+
+  #[test]
+  fn handle_tagged_templates_languages() {
+    let mut config = ConfigKeyMap::new();
+    let mut languages = ConfigKeyMap::new();
+    languages.insert("dbQuery".to_string(), "sql".into());
+    languages.insert("sql".to_string(), ConfigKeyValue::Null);
+    languages.insert("html".to_string(), 5.into());
+    config.insert("taggedTemplates.languages".to_string(), ConfigKeyValue::Object(languages));
+    let result = resolve_config(config, &Default::default());
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].message, "Expected the language of 'html' to be a string or null.");
+    let languages = result.config.tagged_templates_languages;
+    assert_eq!(
+      languages.iter().map(|(tag, language)| (tag.as_str(), language.as_str())).collect::<Vec<_>>(),
+      vec![
+        ("css", "css"),
+        ("dbQuery", "sql"),
+        ("gql", "graphql"),
+        ("graphql", "graphql"),
+        ("html", "html"),
+        ("styled", "css")
+      ]
+    );
+  }
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use dprint_core::configuration::*;
 use dprint_core::generate_str_to_from;
 use serde::Deserialize;
@@ -357,6 +359,13 @@ pub struct Configuration {
   /* ignore comments */
   pub ignore_node_comment_text: String,
   pub ignore_file_comment_text: String,
+  /* verification */
+  pub verify_ast_equivalence: bool,
+  pub verify_idempotency: bool,
+  /* embedded languages */
+  /// Tag names mapped to the language of their tagged templates.
+  #[serde(rename = "taggedTemplates.languages")]
+  pub tagged_templates_languages: BTreeMap<String, String>,
   /* brace position */
   #[serde(rename = "arrowFunction.bracePosition")]
   pub arrow_function_brace_position: BracePosition,
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use deno_ast::ModuleSpecifier;
+use deno_ast::ParseDiagnostic;
+use deno_ast::StartSourcePos;
+
+/// An error that occurred while formatting a file.
+///
+/// The formatting functions return an `anyhow::Error`, which can be
+/// downcast to this type in order to inspect the error.
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+/// use dprint_plugin_typescript::*;
+/// use dprint_plugin_typescript::configuration::*;
+///
+/// let config = ConfigurationBuilder::new().build();
+/// let err = format_text(FormatTextOptions {
+///     path: &PathBuf::from("file.ts"),
+///     extension: None,
+///     text: "const t = ;".into(),
+///     config: &config,
+///     external_formatter: None,
+/// }).unwrap_err();
+/// match err.downcast_ref::<FormatError>() {
+///     Some(FormatError::Syntax(diagnostics)) => {
+///         assert_eq!(diagnostics[0].location.line_number, 1);
+///         assert_eq!(diagnostics[0].location.column_number, 11);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+  /// The file could not be parsed. Contains a diagnostic per syntax error.
+  Syntax(Vec<SyntaxDiagnostic>),
+  /// The external formatter failed formatting an embedded language.
+  ExternalFormatter { location: ErrorLocation, message: String },
+  /// An internal invariant of the formatter was broken. This is a bug.
+  Internal { location: Option<ErrorLocation>, message: String },
+}
+
+impl FormatError {
+  pub fn kind(&self) -> FormatErrorKind {
+    match self {
+      FormatError::Syntax(_) => FormatErrorKind::Syntax,
+      FormatError::ExternalFormatter { .. } => FormatErrorKind::ExternalFormatter,
+      FormatError::Internal { .. } => FormatErrorKind::Internal,
+    }
+  }
+
+  /// Gets the location of the error. For syntax errors, this is the location of the first diagnostic.
+  pub fn location(&self) -> Option<&ErrorLocation> {
+    match self {
+      FormatError::Syntax(diagnostics) => diagnostics.first().map(|d| &d.location),
+      FormatError::ExternalFormatter { location, .. } => Some(location),
+      FormatError::Internal { location, .. } => location.as_ref(),
+    }
+  }
+}
+
+impl std::error::Error for FormatError {}
+
+impl fmt::Display for FormatError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FormatError::Syntax(diagnostics) => {
+        for (i, diagnostic) in diagnostics.iter().enumerate() {
+          if i > 0 {
+            write!(f, "\n\n")?;
+          }
+          write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+      }
+      // the line is displayed zero-indexed for backwards compatibility
+      FormatError::ExternalFormatter { location, message } => {
+        write!(f, "Error formatting tagged template literal at line {}: {}", location.line_number - 1, message)
+      }
+      FormatError::Internal {
+        location: Some(location),
+        message,
+      } => {
+        write!(
+          f,
+          "{}\n    at {}:{}:{}",
+          message, location.specifier, location.line_number, location.column_number
+        )
+      }
+      FormatError::Internal { location: None, message } => write!(f, "{}", message),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatErrorKind {
+  Syntax,
+  ExternalFormatter,
+  Internal,
+}
+
+/// Where in a file an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+  pub specifier: ModuleSpecifier,
+  /// 1-indexed line number.
+  pub line_number: usize,
+  /// 1-indexed column number.
+  pub column_number: usize,
+  /// Byte range in the file text.
+  pub range: Range<usize>,
+}
+
+impl ErrorLocation {
+  /// Gets the file path of the specifier.
+  pub fn file_path(&self) -> PathBuf {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(file_path) = self.specifier.to_file_path() {
+      return file_path;
+    }
+    let path = percent_encoding::percent_decode_str(self.specifier.path()).decode_utf8_lossy();
+    PathBuf::from(path.as_ref())
+  }
+}
+
+/// A syntax error found when parsing the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxDiagnostic {
+  pub location: ErrorLocation,
+  pub diagnostic: ParseDiagnostic,
+}
+
+impl SyntaxDiagnostic {
+  pub(crate) fn new(diagnostic: ParseDiagnostic) -> Self {
+    let position = diagnostic.display_position();
+    SyntaxDiagnostic {
+      location: ErrorLocation {
+        specifier: diagnostic.specifier.clone(),
+        line_number: position.line_number,
+        column_number: position.column_number,
+        range: diagnostic.range.as_byte_range(StartSourcePos::START_SOURCE_POS),
+      },
+      diagnostic,
+    }
+  }
+}
+
+impl fmt::Display for SyntaxDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:#}", self.diagnostic)
+  }
+}
@@ -0,0 +1,216 @@
+//! C API for the dynamic library. See `include/dprint_plugin_typescript.h`.
+
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use anyhow::bail;
+use anyhow::Result;
+
+use super::configuration::resolve_config_file_text;
+use super::configuration::Configuration;
+use super::format_text::format_text;
+use super::format_text::FormatTextOptions;
+use super::utils::get_panic_message;
+
+#[cfg(panic = "abort")]
+compile_error!("The C API reports panics as errors, so build it with `--profile release-ffi` or otherwise with `panic = \"unwind\"`.");
+
+// cbindgen doesn't parenthesize negative values, so these are defined in cbindgen.toml
+
+/// cbindgen:ignore
+pub const DPRINT_TS_UNCHANGED: i32 = 0;
+/// cbindgen:ignore
+pub const DPRINT_TS_CHANGED: i32 = 1;
+/// cbindgen:ignore
+pub const DPRINT_TS_ERROR: i32 = -1;
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+  let message = CString::new(message.replace('\0', "")).unwrap();
+  LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+  LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+}
+
+/// Runs the function, storing the error or panic message for `dprint_ts_last_error`.
+fn run_with_last_error<T>(f: impl FnOnce() -> Result<T>) -> Option<T> {
+  clear_last_error();
+  match catch_unwind(AssertUnwindSafe(f)) {
+    Ok(Ok(value)) => Some(value),
+    Ok(Err(err)) => {
+      set_last_error(format!("{:#}", err));
+      None
+    }
+    Err(panic) => {
+      set_last_error(format!("Formatter panicked: {}", get_panic_message(panic.as_ref())));
+      None
+    }
+  }
+}
+
+unsafe fn get_bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+  if len == 0 {
+    &[]
+  } else {
+    std::slice::from_raw_parts(data, len)
+  }
+}
+
+/// Creates a configuration from the UTF-8 JSON text of a dprint configuration file or
+/// the plugin configuration.
+///
+/// Returns null on error. Free the configuration with `dprint_ts_config_free`.
+///
+/// # Safety
+///
+/// `json_text` must point to `json_text_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_ts_config_new(json_text: *const u8, json_text_len: usize) -> *mut Configuration {
+  let json_text = get_bytes(json_text, json_text_len);
+  run_with_last_error(|| resolve_config_file_text(std::str::from_utf8(json_text)?))
+    .map(|config| Box::into_raw(Box::new(config)))
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Frees a configuration created by `dprint_ts_config_new`.
+///
+/// # Safety
+///
+/// `config` must be null or a configuration from `dprint_ts_config_new` that wasn't freed.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_ts_config_free(config: *mut Configuration) {
+  if !config.is_null() {
+    drop(Box::from_raw(config));
+  }
+}
+
+/// Formats the UTF-8 text of a file. The file path (ex. `file.tsx`) determines how the text is parsed.
+///
+/// Returns `DPRINT_TS_CHANGED` and sets `out_text` and `out_text_len` to the formatted text, which
+/// must be freed with `dprint_ts_text_free`, `DPRINT_TS_UNCHANGED` when the text was already formatted,
+/// or `DPRINT_TS_ERROR` on error.
+///
+/// The configuration may be used from multiple threads at the same time.
+///
+/// # Safety
+///
+/// `config` must be a configuration from `dprint_ts_config_new`, `file_path` a null terminated
+/// string, `text` must point to `text_len` readable bytes and `out_text` and `out_text_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_ts_format(
+  config: *const Configuration,
+  file_path: *const c_char,
+  text: *const u8,
+  text_len: usize,
+  out_text: *mut *mut u8,
+  out_text_len: *mut usize,
+) -> i32 {
+  let text = get_bytes(text, text_len);
+  let result = run_with_last_error(|| {
+    if config.is_null() || file_path.is_null() {
+      bail!("The configuration and file path must not be null.");
+    }
+    let file_path = CStr::from_ptr(file_path).to_str()?;
+    format_text(FormatTextOptions {
+      path: Path::new(file_path),
+      extension: None,
+      text: std::str::from_utf8(text)?.to_string(),
+      config: &*config,
+      external_formatter: None,
+    })
+  });
+  match result {
+    Some(Some(formatted_text)) => {
+      let formatted_text = formatted_text.into_bytes().into_boxed_slice();
+      *out_text_len = formatted_text.len();
+      *out_text = Box::into_raw(formatted_text) as *mut u8;
+      DPRINT_TS_CHANGED
+    }
+    Some(None) => DPRINT_TS_UNCHANGED,
+    None => DPRINT_TS_ERROR,
+  }
+}
+
+/// Frees the formatted text from `dprint_ts_format`.
+///
+/// # Safety
+///
+/// `text` and `text_len` must be null and zero or the formatted text from `dprint_ts_format` that wasn't freed.
+#[no_mangle]
+pub unsafe extern "C" fn dprint_ts_text_free(text: *mut u8, text_len: usize) {
+  if !text.is_null() {
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(text, text_len)));
+  }
+}
+
+/// Gets the error message of the last call on the current thread that failed or null if it succeeded.
+///
+/// The message is valid until the next call on the current thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn dprint_ts_last_error() -> *const c_char {
+  LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map(|message| message.as_ptr()).unwrap_or(std::ptr::null()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn last_error() -> Option<String> {
+    let message = dprint_ts_last_error();
+    (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) }.to_str().unwrap().to_string())
+  }
+
+  fn format(config: *const Configuration, file_path: &str, text: &str) -> (i32, Option<String>) {
+    let file_path = CString::new(file_path).unwrap();
+    let mut out_text = std::ptr::null_mut();
+    let mut out_text_len = 0;
+    unsafe {
+      let status = dprint_ts_format(config, file_path.as_ptr(), text.as_ptr(), text.len(), &mut out_text, &mut out_text_len);
+      let formatted_text = (status == DPRINT_TS_CHANGED).then(|| std::str::from_utf8(std::slice::from_raw_parts(out_text, out_text_len)).unwrap().to_string());
+      dprint_ts_text_free(out_text, out_text_len);
+      (status, formatted_text)
+    }
+  }
+
+  #[test]
+  fn formats_with_c_api() {
+    let json = r#"{ "lineWidth": 40, "semiColons": "asi" }"#;
+    let config = unsafe { dprint_ts_config_new(json.as_ptr(), json.len()) };
+    assert!(!config.is_null());
+    assert_eq!(last_error(), None);
+
+    assert_eq!(
+      format(config, "file.ts", "const  a = 1;"),
+      (DPRINT_TS_CHANGED, Some("const a = 1\n".to_string()))
+    );
+    assert_eq!(format(config, "file.ts", "const a = 1\n"), (DPRINT_TS_UNCHANGED, None));
+    assert_eq!(last_error(), None);
+    assert_eq!(format(config, "file.ts", "const a = ;"), (DPRINT_TS_ERROR, None));
+    assert!(last_error().unwrap().starts_with("Expression expected"));
+    assert_eq!(format(std::ptr::null(), "file.ts", ""), (DPRINT_TS_ERROR, None));
+    assert_eq!(last_error().unwrap(), "The configuration and file path must not be null.");
+    unsafe { dprint_ts_config_free(config) };
+
+    let json = r#"{ "unknown": 1 }"#;
+    assert!(unsafe { dprint_ts_config_new(json.as_ptr(), json.len()) }.is_null());
+    assert_eq!(last_error().unwrap(), "Invalid configuration:\n  Unknown property in configuration (unknown)");
+  }
+
+  #[test]
+  fn reports_panics() {
+    assert_eq!(run_with_last_error(|| -> Result<()> { panic!("Some panic.") }), None);
+    assert_eq!(last_error().unwrap(), "Formatter panicked: Some panic.");
+    assert_eq!(run_with_last_error(|| Ok(1)), Some(1));
+    assert_eq!(last_error(), None);
+  }
+}
@@ -0,0 +1,404 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use dprint_core::configuration::resolve_new_line_kind;
+use dprint_core::configuration::NewLineKind;
+
+use super::error::ErrorLocation;
+use super::error::FormatError;
+use super::format_text::format_text;
+use super::format_text::FormatTextOptions;
+
+/// Formats the scripts of a Vue (`.vue`), Svelte (`.svelte`) or Astro (`.astro`) file.
+///
+/// The `<script>` blocks with JavaScript or TypeScript and the frontmatter of Astro files
+/// are formatted, while the rest of the file is left as-is. Scripts without a `lang` are
+/// JavaScript, except in Astro files where they're TypeScript. Each block keeps its indentation
+/// by being formatted with a `file_indent_level` of its current indentation.
+///
+/// The extension is used to know the kind of file and defaults to the extension of the path.
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+/// use dprint_plugin_typescript::*;
+/// use dprint_plugin_typescript::configuration::*;
+///
+/// let config = ConfigurationBuilder::new().build();
+/// let result = format_component_text(FormatTextOptions {
+///     path: &PathBuf::from("App.vue"),
+///     extension: None,
+///     text: "<template><div /></template>\n<script setup lang=\"ts\">\nconst  a  =  1 ;\n</script>\n".into(),
+///     config: &config,
+///     external_formatter: None,
+/// }).unwrap();
+/// assert_eq!(
+///     result.unwrap(),
+///     "<template><div /></template>\n<script setup lang=\"ts\">\nconst a = 1;\n</script>\n",
+/// );
+/// ```
+pub fn format_component_text(options: FormatTextOptions) -> Result<Option<String>> {
+  let FormatTextOptions {
+    path: file_path,
+    extension: file_extension,
+    text: file_text,
+    config,
+    external_formatter,
+  } = options;
+  let extension = file_extension
+    .map(|ext| ext.to_lowercase())
+    .or_else(|| file_path.extension().map(|ext| ext.to_string_lossy().to_lowercase()));
+  let blocks = match extension.as_deref() {
+    Some("vue") | Some("svelte") => get_script_blocks(&file_text, 0, "js"),
+    Some("astro") => get_astro_script_blocks(&file_text),
+    _ => anyhow::bail!("Expected a .vue, .svelte or .astro file: {}", file_path.display()),
+  };
+
+  // the blocks use the new line kind of the file, which may be detected from it
+  let new_line_text = resolve_new_line_kind(&file_text, config.new_line_kind);
+  let new_line_kind = if new_line_text == "\r\n" {
+    NewLineKind::CarriageReturnLineFeed
+  } else {
+    NewLineKind::LineFeed
+  };
+  let mut new_text = String::with_capacity(file_text.len());
+  let mut last_end = 0;
+  for block in blocks {
+    let block_text = &file_text[block.range.clone()];
+    if block_text.trim().is_empty() {
+      continue;
+    }
+    let mut block_config = config.clone();
+    block_config.file_indent_level = get_indent_level(block_text, config.indent_width);
+    block_config.new_line_kind = new_line_kind;
+    let formatted_text = format_text(FormatTextOptions {
+      path: file_path,
+      extension: Some(block.extension),
+      text: block_text.trim_start_matches(['\r', '\n']).to_string(),
+      config: &block_config,
+      external_formatter,
+    })
+    .map_err(|err| shift_error_locations(err, &file_text, block.range.start + get_leading_new_lines_len(block_text)))?;
+    let Some(formatted_text) = formatted_text else {
+      continue;
+    };
+    new_text.push_str(&file_text[last_end..block.range.start]);
+    new_text.push_str(new_line_text);
+    new_text.push_str(&formatted_text);
+    // keep the indentation of the closing tag or fence
+    new_text.push_str(&block_text[block_text.trim_end_matches([' ', '\t']).len()..]);
+    last_end = block.range.end;
+  }
+  new_text.push_str(&file_text[last_end..]);
+
+  if new_text == file_text {
+    Ok(None)
+  } else {
+    Ok(Some(new_text))
+  }
+}
+
+struct ScriptBlock {
+  /// Byte range of the text between the opening and closing tags or fences.
+  range: Range<usize>,
+  extension: &'static str,
+}
+
+fn get_astro_script_blocks(text: &str) -> Vec<ScriptBlock> {
+  let mut blocks = Vec::new();
+  let mut template_start = 0;
+  let trimmed_text = text.trim_start();
+  if let Some(after_fence) = trimmed_text.strip_prefix("---") {
+    let start = text.len() - after_fence.len();
+    if let Some(end) = find_closing_fence(text, start) {
+      blocks.push(ScriptBlock {
+        range: start..end,
+        extension: "ts",
+      });
+      template_start = end + 3;
+    }
+  }
+  blocks.extend(get_script_blocks(text, template_start, "ts"));
+  blocks
+}
+
+/// Finds the start of the `---` line that closes the frontmatter.
+fn find_closing_fence(text: &str, start: usize) -> Option<usize> {
+  let mut pos = start;
+  for line in text[start..].split_inclusive('\n') {
+    if pos > start && line.trim_end() == "---" {
+      return Some(pos);
+    }
+    pos += line.len();
+  }
+  None
+}
+
+/// Gets the script blocks, where a script without a `lang` uses the default extension.
+fn get_script_blocks(text: &str, start: usize, default_extension: &'static str) -> Vec<ScriptBlock> {
+  let lowercase_text = text.to_ascii_lowercase();
+  let mut blocks = Vec::new();
+  let mut pos = start;
+  while let Some(index) = lowercase_text[pos..].find('<') {
+    let tag_start = pos + index;
+    let remaining = &lowercase_text[tag_start..];
+    if remaining.starts_with("<!--") {
+      pos = match remaining.find("-->") {
+        Some(end) => tag_start + end + 3,
+        None => break,
+      };
+      continue;
+    }
+    let is_script_tag = remaining
+      .strip_prefix("<script")
+      .is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '>'));
+    if !is_script_tag {
+      pos = tag_start + 1;
+      continue;
+    }
+    let Some((attributes, open_end)) = parse_tag_attributes(text, tag_start + "<script".len()) else {
+      break;
+    };
+    let Some(close_index) = lowercase_text[open_end..].find("</script") else {
+      break;
+    };
+    let close_start = open_end + close_index;
+    if let Some(extension) = get_script_extension(&attributes, default_extension) {
+      blocks.push(ScriptBlock {
+        range: open_end..close_start,
+        extension,
+      });
+    }
+    pos = close_start + "</script".len();
+  }
+  blocks
+}
+
+/// Parses the attributes of a tag, returning them along with the position after the `>`.
+fn parse_tag_attributes(text: &str, start: usize) -> Option<(Vec<(String, String)>, usize)> {
+  let mut attributes = Vec::new();
+  let mut chars = text[start..].char_indices().peekable();
+  loop {
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    let (index, c) = chars.next()?;
+    match c {
+      '>' => return Some((attributes, start + index + 1)),
+      '/' => continue,
+      _ => {}
+    }
+    let mut name = c.to_string();
+    while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !matches!(c, '=' | '>' | '/')) {
+      name.push(c);
+    }
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    let mut value = String::new();
+    if chars.next_if(|(_, c)| *c == '=').is_some() {
+      while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+      match chars.next_if(|(_, c)| matches!(c, '"' | '\'')) {
+        Some((_, quote)) => loop {
+          let (_, c) = chars.next()?;
+          if c == quote {
+            break;
+          }
+          value.push(c);
+        },
+        None => {
+          while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '>') {
+            value.push(c);
+          }
+        }
+      }
+    }
+    attributes.push((name.to_ascii_lowercase(), value));
+  }
+}
+
+/// Gets the extension to parse a script with or `None` when it isn't JavaScript or TypeScript.
+fn get_script_extension(attributes: &[(String, String)], default_extension: &'static str) -> Option<&'static str> {
+  let get_attribute = |name: &str| attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.to_ascii_lowercase());
+  if attributes.iter().any(|(key, _)| key == "src") {
+    return None;
+  }
+  match get_attribute("type").as_deref() {
+    None | Some("") | Some("module") | Some("text/javascript") | Some("application/javascript") | Some("text/typescript") => {}
+    _ => return None,
+  }
+  match get_attribute("lang").as_deref() {
+    None | Some("") => Some(default_extension),
+    Some("js") | Some("javascript") => Some("js"),
+    Some("ts") | Some("typescript") => Some("ts"),
+    Some("jsx") => Some("jsx"),
+    Some("tsx") => Some("tsx"),
+    _ => None,
+  }
+}
+
+/// Gets the indent level of the first non-blank line.
+fn get_indent_level(text: &str, indent_width: u8) -> u32 {
+  let Some(line) = text.lines().find(|line| !line.trim().is_empty()) else {
+    return 0;
+  };
+  let mut width = 0;
+  for c in line.chars() {
+    match c {
+      ' ' => width += 1,
+      '\t' => width += indent_width as u32,
+      _ => break,
+    }
+  }
+  width / std::cmp::max(indent_width as u32, 1)
+}
+
+fn get_leading_new_lines_len(text: &str) -> usize {
+  text.len() - text.trim_start_matches(['\r', '\n']).len()
+}
+
+/// Updates the locations of errors to be relative to the file instead of the block.
+fn shift_error_locations(err: anyhow::Error, file_text: &str, block_start: usize) -> anyhow::Error {
+  let text_before = &file_text[..block_start];
+  let line_offset = text_before.matches('\n').count();
+  let column_offset = text_before.rsplit('\n').next().unwrap_or_default().chars().count();
+  let shift_location = |location: &mut ErrorLocation| {
+    if location.line_number == 1 {
+      location.column_number += column_offset;
+    }
+    location.line_number += line_offset;
+    location.range = location.range.start + block_start..location.range.end + block_start;
+  };
+  match err.downcast::<FormatError>() {
+    Ok(mut err) => {
+      match &mut err {
+        FormatError::Syntax(diagnostics) => {
+          for diagnostic in diagnostics.iter_mut() {
+            shift_location(&mut diagnostic.location);
+          }
+        }
+        FormatError::ExternalFormatter { location, .. } => shift_location(location),
+        FormatError::Internal { location, .. } => {
+          if let Some(location) = location {
+            shift_location(location);
+          }
+        }
+      }
+      err.into()
+    }
+    Err(err) => err,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+
+  use super::*;
+  use crate::configuration::Configuration;
+  use crate::configuration::ConfigurationBuilder;
+
+  fn format(file_name: &str, text: &str) -> Result<Option<String>> {
+    format_with_config(file_name, text, &ConfigurationBuilder::new().build())
+  }
+
+  fn format_with_config(file_name: &str, text: &str, config: &Configuration) -> Result<Option<String>> {
+    format_component_text(FormatTextOptions {
+      path: &PathBuf::from(file_name),
+      extension: None,
+      text: text.to_string(),
+      config,
+      external_formatter: None,
+    })
+  }
+
+  #[test]
+  fn formats_vue_scripts() {
+    assert_eq!(
+      format(
+        "App.vue",
+        "<template>\n  <div>{{ a }}</div>\n</template>\n\n<script lang=\"ts\">\nexport default {  }\n</script>\n<script setup lang='ts'>\nconst  a = <T,>(b: T) => b\n</script>\n<style>\na { }\n</style>\n"
+      )
+      .unwrap()
+      .unwrap(),
+      "<template>\n  <div>{{ a }}</div>\n</template>\n\n<script lang=\"ts\">\nexport default {};\n</script>\n<script setup lang='ts'>\nconst a = <T>(b: T) => b;\n</script>\n<style>\na { }\n</style>\n"
+    );
+    assert_eq!(format("App.vue", "<script>\nconst a = 1;\n</script>\n").unwrap(), None);
+  }
+
+  #[test]
+  fn formats_svelte_scripts_with_indentation() {
+    assert_eq!(
+      format(
+        "App.svelte",
+        "<script context=\"module\">\n  export const  a = 1\n</script>\n<script lang=\"ts\">\n\tlet  b : number\n\tif (b) { b++ }\n  </script>\n<!-- <script>let  c</script> -->\n<script type=\"application/json\">{ \"a\":1 }</script>\n<p>{b}</p>\n"
+      )
+      .unwrap()
+      .unwrap(),
+      "<script context=\"module\">\n  export const a = 1;\n</script>\n<script lang=\"ts\">\n  let b: number;\n  if (b) b++;\n  </script>\n<!-- <script>let  c</script> -->\n<script type=\"application/json\">{ \"a\":1 }</script>\n<p>{b}</p>\n"
+    );
+  }
+
+  #[test]
+  fn formats_astro_frontmatter_and_scripts() {
+    assert_eq!(
+      format(
+        "page.astro",
+        "---\nimport  Layout from '../layouts/Layout.astro'\nconst { title } = Astro.props\n---\n<Layout>\n<script>\n  const  title : string = 'a'\n</script>\n<script lang=\"js\">\n  let  a\n</script>\n</Layout>\n"
+      )
+      .unwrap()
+      .unwrap(),
+      "---\nimport Layout from \"../layouts/Layout.astro\";\nconst { title } = Astro.props;\n---\n<Layout>\n<script>\n  const title: string = \"a\";\n</script>\n<script lang=\"js\">\n  let a;\n</script>\n</Layout>\n"
+    );
+    // scripts without a lang are JavaScript in other files
+    assert!(format("App.vue", "<script>\nconst  a : string = 'a'\n</script>\n").is_err());
+  }
+
+  #[test]
+  fn uses_new_line_kind() {
+    let text = "<template />\r\n<script setup lang=\"ts\">\r\nconst  a = 1\r\n</script>\r\n";
+    let auto_config = ConfigurationBuilder::new().new_line_kind(NewLineKind::Auto).build();
+    assert_eq!(
+      format_with_config("App.vue", text, &auto_config).unwrap().unwrap(),
+      "<template />\r\n<script setup lang=\"ts\">\r\nconst a = 1;\r\n</script>\r\n"
+    );
+    let crlf_config = ConfigurationBuilder::new().new_line_kind(NewLineKind::CarriageReturnLineFeed).build();
+    assert_eq!(
+      format_with_config("App.vue", "<template />\n<script>\nlet  a\n</script>\n", &crlf_config)
+        .unwrap()
+        .unwrap(),
+      "<template />\n<script>\r\nlet a;\r\n</script>\n"
+    );
+  }
+
+  #[test]
+  fn errors_with_file_locations() {
+    let err = format("App.vue", "<template />\n<script>\nconst a = 1;\nconst b = ;\n</script>\n").unwrap_err();
+    match err.downcast_ref::<FormatError>().unwrap() {
+      FormatError::Syntax(diagnostics) => {
+        assert_eq!(diagnostics[0].location.line_number, 4);
+        assert_eq!(diagnostics[0].location.column_number, 11);
+        assert_eq!(diagnostics[0].location.range.start, 45);
+        assert_eq!(diagnostics[0].location.file_path().file_name().unwrap(), "App.vue");
+      }
+      _ => unreachable!(),
+    }
+    assert!(format("file.ts", "").is_err());
+
+    let external_formatter = |_: crate::ExternalFormatRequest| anyhow::bail!("Some error.");
+    let err = format_component_text(FormatTextOptions {
+      path: &PathBuf::from("App.vue"),
+      extension: None,
+      text: "<template />\n<script>\nconst a = 1;\nconst b = css`a{}`;\n</script>\n".to_string(),
+      config: &ConfigurationBuilder::new().build(),
+      external_formatter: Some(&external_formatter),
+    })
+    .unwrap_err();
+    match err.downcast_ref::<FormatError>().unwrap() {
+      FormatError::ExternalFormatter { location, message } => {
+        assert_eq!(location.line_number, 4);
+        assert_eq!(location.column_number, 11);
+        assert_eq!(location.range, 45..53);
+        assert_eq!(message, "Some error.");
+      }
+      _ => unreachable!(),
+    }
+  }
+}
@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use deno_ast::view::*;
+use deno_ast::MediaType;
+use deno_ast::ModuleSpecifier;
+use deno_ast::ParsedSource;
+use deno_ast::SourceRanged;
+use dprint_core::configuration::resolve_new_line_kind;
+use dprint_core::formatting::ir_helpers::with_indent_times;
+use dprint_core::formatting::*;
+
+use super::configuration::Configuration;
+use super::error::FormatError;
+use super::generation::generate_block_statements;
+use super::generation::generate_node;
+use super::generation::ExternalFormatter;
+use super::swc::ensure_no_specific_syntax_errors;
+use super::swc::parse_with_media_type;
+
+pub struct FormatSnippetOptions<'a> {
+  pub text: &'a str,
+  /// How to parse the snippet (ex. `MediaType::Tsx` to support JSX).
+  pub media_type: MediaType,
+  pub config: &'a Configuration,
+  pub external_formatter: Option<&'a ExternalFormatter<'a>>,
+  /// Indent level of the lines after the first line.
+  pub indent_level: u32,
+  /// Column the first line starts at. This reduces the width available to the first line.
+  pub start_column: u32,
+}
+
+/// Formats a single expression.
+///
+/// The returned text has no leading indentation and no trailing newline.
+///
+/// # Example
+///
+/// ```
+/// use dprint_plugin_typescript::*;
+/// use dprint_plugin_typescript::configuration::*;
+///
+/// let config = ConfigurationBuilder::new().build();
+/// let text = format_expression(FormatSnippetOptions {
+///     text: "{a:1,b:[ 2,3 ]}",
+///     media_type: deno_ast::MediaType::TypeScript,
+///     config: &config,
+///     external_formatter: None,
+///     indent_level: 0,
+///     start_column: 0,
+/// }).unwrap();
+/// assert_eq!(text, "{ a: 1, b: [2, 3] }");
+/// ```
+pub fn format_expression(options: FormatSnippetOptions) -> Result<String> {
+  format_snippet(options, SnippetKind::Expression)
+}
+
+/// Formats a single type.
+///
+/// The returned text has no leading indentation and no trailing newline.
+pub fn format_type(options: FormatSnippetOptions) -> Result<String> {
+  format_snippet(options, SnippetKind::Type)
+}
+
+/// Formats statements and declarations as the body of a function, so the statements
+/// may `return`, `await` or `yield`.
+///
+/// The returned text has no leading indentation and no trailing newline.
+pub fn format_statements(options: FormatSnippetOptions) -> Result<String> {
+  format_snippet(options, SnippetKind::Statements)
+}
+
+#[derive(Clone, Copy)]
+enum SnippetKind {
+  Expression,
+  Type,
+  Statements,
+}
+
+impl SnippetKind {
+  /// Gets the text to wrap the snippet in so it parses as a module.
+  ///
+  /// The new lines ensure a trailing line comment doesn't comment out the end.
+  fn wrapper(&self) -> (&'static str, &'static str) {
+    match self {
+      // a variable's initializer is used because an expression statement would add parens to object literals
+      SnippetKind::Expression => ("const e = (", "\n);"),
+      SnippetKind::Type => ("type T = ", "\n;"),
+      SnippetKind::Statements => ("async function* f() {\n", "\n}"),
+    }
+  }
+
+  fn description(&self) -> &'static str {
+    match self {
+      SnippetKind::Expression => "a single expression",
+      SnippetKind::Type => "a single type",
+      SnippetKind::Statements => "the statements of a function body",
+    }
+  }
+}
+
+fn format_snippet(options: FormatSnippetOptions, kind: SnippetKind) -> Result<String> {
+  let FormatSnippetOptions {
+    text,
+    media_type,
+    config,
+    external_formatter,
+    indent_level,
+    start_column,
+  } = options;
+  let (prefix, suffix) = kind.wrapper();
+  let wrapped_text: Arc<str> = format!("{}{}{}", prefix, text, suffix).into();
+  let specifier = ModuleSpecifier::parse(&format!("file:///snippet{}", media_type.as_ts_extension()))?;
+  let parsed_source = parse_with_media_type(specifier, media_type, wrapped_text.clone())
+    .and_then(|parsed_source| {
+      ensure_no_specific_syntax_errors(&parsed_source)?;
+      Ok(parsed_source)
+    })
+    .map_err(|err| unwrap_syntax_error_locations(err, prefix.len(), text))?;
+
+  let mut maybe_err: Box<Option<anyhow::Error>> = Box::new(None);
+  let mut found_node = true;
+  let result = dprint_core::formatting::format(
+    || {
+      let result = match kind {
+        SnippetKind::Statements => generate_block_statements(&parsed_source, config, external_formatter, |program| get_snippet_block(program, &parsed_source)),
+        SnippetKind::Expression | SnippetKind::Type => generate_node(&parsed_source, config, external_formatter, |program| {
+          get_snippet_node(program, kind, &parsed_source)
+        }),
+      };
+      match result {
+        Ok(Some(items)) => {
+          let mut new_items = PrintItems::new();
+          // reserve the start column on the first line
+          if start_column > 0 {
+            new_items.push_string(" ".repeat(start_column as usize));
+          }
+          new_items.extend(with_indent_times(items, indent_level));
+          new_items
+        }
+        Ok(None) => {
+          found_node = false;
+          PrintItems::default()
+        }
+        Err(e) => {
+          maybe_err.replace(e);
+          PrintItems::default()
+        }
+      }
+    },
+    PrintOptions {
+      indent_width: config.indent_width,
+      max_width: config.line_width,
+      use_tabs: config.use_tabs,
+      new_line_text: resolve_new_line_kind(text, config.new_line_kind),
+    },
+  );
+  if let Some(e) = maybe_err.take() {
+    return Err(e);
+  }
+  if !found_node {
+    bail!("Expected {}, but found: {}", kind.description(), text.trim());
+  }
+  // remove the start column or indentation of the first line (ex. a type that starts on the next line)
+  Ok(result.trim().to_string())
+}
+
+fn get_snippet_node<'a>(program: Program<'a>, kind: SnippetKind, parsed_source: &ParsedSource) -> Option<Node<'a>> {
+  let wrapped_len = parsed_source.text().len();
+  let source_start = parsed_source.range().start;
+  let prefix_len = kind.wrapper().0.len();
+  let items = match program {
+    Program::Module(module) => module.body.iter().map(|item| item.into()).collect::<Vec<Node>>(),
+    Program::Script(script) => script.body.iter().map(|item| item.into()).collect::<Vec<Node>>(),
+  };
+  match kind {
+    SnippetKind::Statements => None,
+    SnippetKind::Expression => {
+      let [Node::VarDecl(var_decl)] = items.as_slice() else {
+        return None;
+      };
+      let [declarator] = var_decl.decls else {
+        return None;
+      };
+      let Some(Expr::Paren(paren_expr)) = declarator.init else {
+        return None;
+      };
+      // ensure the parens are the ones that wrap the snippet (ex. not `a); const f = (b`)
+      let paren_range = paren_expr.range().as_byte_range(source_start);
+      (paren_range.start == prefix_len - 1 && paren_range.end == wrapped_len - 1).then(|| paren_expr.expr.into())
+    }
+    SnippetKind::Type => {
+      let [Node::TsTypeAliasDecl(type_alias)] = items.as_slice() else {
+        return None;
+      };
+      (type_alias.type_params.is_none() && type_alias.range().as_byte_range(source_start).end == wrapped_len).then(|| type_alias.type_ann.into())
+    }
+  }
+}
+
+/// Gets the body of the function that wraps the statements.
+fn get_snippet_block<'a>(program: Program<'a>, parsed_source: &ParsedSource) -> Option<&'a BlockStmt<'a>> {
+  let items = match program {
+    Program::Module(module) => module.body.iter().map(|item| item.into()).collect::<Vec<Node>>(),
+    Program::Script(script) => script.body.iter().map(|item| item.into()).collect::<Vec<Node>>(),
+  };
+  let [Node::FnDecl(fn_decl)] = items.as_slice() else {
+    return None;
+  };
+  let body = fn_decl.function.body?;
+  // ensure the body is the one that wraps the snippet (ex. not `}; function g() {`)
+  let body_range = body.range().as_byte_range(parsed_source.range().start);
+  let prefix_len = SnippetKind::Statements.wrapper().0.len();
+  (body_range.start == prefix_len - 2 && body_range.end == parsed_source.text().len()).then_some(body)
+}
+
+/// Updates the locations of syntax errors to be relative to the snippet instead of the wrapped snippet.
+fn unwrap_syntax_error_locations(err: anyhow::Error, prefix_len: usize, text: &str) -> anyhow::Error {
+  match err.downcast::<FormatError>() {
+    Ok(FormatError::Syntax(mut diagnostics)) => {
+      for diagnostic in diagnostics.iter_mut() {
+        let location = &mut diagnostic.location;
+        let start = location.range.start.saturating_sub(prefix_len).min(text.len());
+        let end = location.range.end.saturating_sub(prefix_len).min(text.len());
+        let text_before = &text[..start];
+        location.range = start..end;
+        location.line_number = text_before.matches('\n').count() + 1;
+        location.column_number = text_before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+      }
+      FormatError::Syntax(diagnostics).into()
+    }
+    Ok(err) => err.into(),
+    Err(err) => err,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::configuration::ConfigurationBuilder;
+
+  fn run_test(format: fn(FormatSnippetOptions) -> Result<String>, text: &str, indent_level: u32, start_column: u32, expected: &str) {
+    let config = ConfigurationBuilder::new().line_width(40).build();
+    let result = format(FormatSnippetOptions {
+      text,
+      media_type: MediaType::Tsx,
+      config: &config,
+      external_formatter: None,
+      indent_level,
+      start_column,
+    })
+    .unwrap();
+    assert_eq!(result, expected);
+  }
+
+  #[test]
+  fn formats_expression() {
+    run_test(format_expression, "a+ b", 0, 0, "a + b");
+    run_test(format_expression, "{a:1}", 0, 0, "{ a: 1 }");
+    run_test(format_expression, "a // test", 0, 0, "a // test");
+    run_test(format_expression, "<div>test</div>", 0, 0, "<div>test</div>");
+    run_test(
+      format_expression,
+      "call(aaaaaaaaaaaa, bbbbbbbbbbbbb)",
+      1,
+      20,
+      "call(\n    aaaaaaaaaaaa,\n    bbbbbbbbbbbbb,\n  )",
+    );
+    run_test(
+      format_expression,
+      "call(aaaaaaaaaaaa, bbbbbbbbbbbbb)",
+      1,
+      0,
+      "call(aaaaaaaaaaaa, bbbbbbbbbbbbb)",
+    );
+  }
+
+  #[test]
+  fn formats_type() {
+    run_test(format_type, "{a:string,b:number}", 0, 0, "{ a: string; b: number }");
+    run_test(format_type, "Array< string >", 0, 0, "Array<string>");
+    run_test(
+      format_type,
+      "'aaaaaaaaaa' | 'bbbbbbbbbb' | 'cccccccccc' | 'dddddddddd'",
+      0,
+      10,
+      "| \"aaaaaaaaaa\"\n  | \"bbbbbbbbbb\"\n  | \"cccccccccc\"\n  | \"dddddddddd\"",
+    );
+  }
+
+  #[test]
+  fn formats_statements() {
+    run_test(format_statements, "let  a = 1;\n\n\nif(a){b()}", 0, 0, "let a = 1;\n\nif (a) b();");
+    run_test(format_statements, "let  a = 1;\nlet b = 2;", 1, 2, "let a = 1;\n  let b = 2;");
+    run_test(format_statements, "let  a = 1;\nlet b = 2;", 1, 0, "let a = 1;\n  let b = 2;");
+    run_test(
+      format_statements,
+      "if(a){return  1}\nreturn await  b;",
+      0,
+      0,
+      "if (a) return 1;\nreturn await b;",
+    );
+    run_test(format_statements, "// test\nyield  a; // other", 0, 0, "// test\nyield a; // other");
+  }
+
+  #[test]
+  fn errors_for_invalid_snippets() {
+    let config = ConfigurationBuilder::new().build();
+    let get_err = |format: fn(FormatSnippetOptions) -> Result<String>, text: &str| {
+      format(FormatSnippetOptions {
+        text,
+        media_type: MediaType::TypeScript,
+        config: &config,
+        external_formatter: None,
+        indent_level: 0,
+        start_column: 0,
+      })
+      .unwrap_err()
+    };
+    assert_eq!(
+      get_err(format_expression, "a); const f = (b").to_string(),
+      "Expected a single expression, but found: a); const f = (b"
+    );
+    assert_eq!(
+      get_err(format_statements, "a; }; function g() { b;").to_string(),
+      "Expected the statements of a function body, but found: a; }; function g() { b;"
+    );
+    assert_eq!(
+      get_err(format_type, "string; let a = 1").to_string(),
+      "Expected a single type, but found: string; let a = 1"
+    );
+    match get_err(format_expression, "a +\n  ;").downcast_ref::<FormatError>().unwrap() {
+      FormatError::Syntax(diagnostics) => {
+        assert_eq!(diagnostics[0].location.line_number, 2);
+        assert_eq!(diagnostics[0].location.column_number, 3);
+      }
+      _ => unreachable!(),
+    }
+  }
+}
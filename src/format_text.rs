@@ -1,24 +1,45 @@
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Result;
+use deno_ast::view::*;
 use deno_ast::ParsedSource;
+use deno_ast::SourceRanged;
+use deno_ast::SourceTextInfoProvider;
 use dprint_core::configuration::resolve_new_line_kind;
+use dprint_core::configuration::NewLineKind;
 use dprint_core::formatting::*;
 
 use crate::swc::ensure_no_specific_syntax_errors;
 
+use super::ast_equivalence::ensure_ast_equivalence;
 use super::configuration::Configuration;
-use super::generation::generate;
+use super::configuration::SortOrder;
+use super::error::ErrorLocation;
+use super::error::FormatError;
+pub use super::generation::adapt_legacy_external_formatter;
+use super::generation::generate_with_diagnostics;
+pub use super::generation::ExternalFormatRequest;
 pub use super::generation::ExternalFormatter;
+pub use super::generation::LegacyExternalFormatter;
+use super::glimmer::ensure_not_glimmer_file;
+use super::glimmer::get_glimmer_script_extension;
+use super::glimmer::GlimmerTemplates;
+use super::offset_map::OffsetAnchors;
+use super::offset_map::OffsetMap;
 use super::swc::parse_swc_ast;
+use super::swc::parse_swc_ast_lenient;
+use super::swc::reparse_swc_ast;
+use super::text_edit::get_text_edits;
+use super::text_edit::TextEdit;
 
 pub struct FormatTextOptions<'a> {
   pub path: &'a Path,
   pub extension: Option<&'a str>,
   pub text: String,
   pub config: &'a Configuration,
-  pub external_formatter: Option<&'a ExternalFormatter>,
+  pub external_formatter: Option<&'a ExternalFormatter<'a>>,
 }
 
 /// Formats a file.
@@ -41,7 +62,7 @@ pub struct FormatTextOptions<'a> {
 ///     .next_control_flow_position(NextControlFlowPosition::SameLine)
 ///     .build();
 ///
-/// // now format many files (it is recommended to parallelize this)
+/// // now format many files (it is recommended to parallelize this, which `Formatter::format_many` does)
 /// let files_to_format = vec![(PathBuf::from("path/to/file.ts"), "const  t  =  5 ;")];
 /// for (file_path, file_text) in files_to_format {
 ///     let result = format_text(FormatTextOptions {
@@ -55,6 +76,34 @@ pub struct FormatTextOptions<'a> {
 /// }
 /// ```
 pub fn format_text(options: FormatTextOptions) -> Result<Option<String>> {
+  let print_options = ConfigPrintOptions::new(options.config);
+  format_text_collecting_warnings(options, None, &print_options)
+}
+
+pub struct FormatTextWithWarningsResult {
+  /// The formatted file text or `None` when it did not change.
+  pub text: Option<String>,
+  /// The external formatter failures. The templates it failed on were left as-is.
+  pub warnings: Vec<FormatError>,
+}
+
+/// Formats a file without failing when the external formatter fails.
+///
+/// Embedded languages the external formatter fails on or whose expression placeholders
+/// it doesn't keep are left as-is and all the failures are returned as warnings along
+/// with the formatted text.
+pub fn format_text_with_warnings(options: FormatTextOptions) -> Result<FormatTextWithWarningsResult> {
+  let mut warnings = Vec::new();
+  let print_options = ConfigPrintOptions::new(options.config);
+  let text = format_text_collecting_warnings(options, Some(&mut warnings), &print_options)?;
+  Ok(FormatTextWithWarningsResult { text, warnings })
+}
+
+pub(crate) fn format_text_collecting_warnings(
+  options: FormatTextOptions,
+  warnings: Option<&mut Vec<FormatError>>,
+  print_options: &ConfigPrintOptions,
+) -> Result<Option<String>> {
   let FormatTextOptions {
     path: file_path,
     extension: file_extension,
@@ -67,9 +116,29 @@ pub fn format_text(options: FormatTextOptions) -> Result<Option<String>> {
   } else {
     let had_bom = file_text.starts_with("\u{FEFF}");
     let file_text = if had_bom { file_text[3..].to_string() } else { file_text };
+    if let Some(script_extension) = get_glimmer_script_extension(file_path, file_extension) {
+      // format the script with the <template> blocks replaced by placeholders
+      let templates = GlimmerTemplates::extract(&file_text);
+      let formatted_script_text = format_text_collecting_warnings(
+        FormatTextOptions {
+          path: file_path,
+          extension: Some(script_extension),
+          text: templates.script_text.clone(),
+          config,
+          external_formatter,
+        },
+        warnings,
+        print_options,
+      )?;
+      let new_text = match formatted_script_text {
+        Some(formatted_script_text) => templates.restore(&formatted_script_text)?,
+        None => file_text.clone(),
+      };
+      return Ok(if had_bom || new_text != file_text { Some(new_text) } else { None });
+    }
     let file_text: Arc<str> = file_text.into();
     let parsed_source = parse_swc_ast(file_path, file_extension, file_text)?;
-    match inner_format(&parsed_source, config, external_formatter)? {
+    match inner_format(&parsed_source, config, print_options, external_formatter, warnings, None)? {
       Some(new_text) => Ok(Some(new_text)),
       None => {
         if had_bom {
@@ -82,35 +151,577 @@ pub fn format_text(options: FormatTextOptions) -> Result<Option<String>> {
   }
 }
 
+pub struct FormatTextWithOffsetMapResult {
+  /// The formatted file text.
+  pub text: String,
+  /// Maps byte offsets in the original text to byte offsets in `text`.
+  pub offset_map: OffsetMap,
+}
+
+/// Formats a file and provides a map of original to formatted byte offsets.
+///
+/// This is useful for keeping cursor positions, selections and diagnostics
+/// anchored after formatting.
+///
+/// Returns `None` when the file text did not change.
+pub fn format_text_with_offset_map(options: FormatTextOptions) -> Result<Option<FormatTextWithOffsetMapResult>> {
+  let FormatTextOptions {
+    path: file_path,
+    extension: file_extension,
+    text: file_text,
+    config,
+    external_formatter,
+  } = options;
+  ensure_not_glimmer_file(file_path, file_extension, "Mapping offsets")?;
+  if super::utils::file_text_has_ignore_comment(&file_text, &config.ignore_file_comment_text) {
+    return Ok(None);
+  }
+
+  let bom_len = if file_text.starts_with("\u{FEFF}") { 3 } else { 0 };
+  let parsed_source = parse_swc_ast(file_path, file_extension, file_text[bom_len..].into())?;
+  let offset_anchors = OffsetAnchors::default();
+  let print_options = ConfigPrintOptions::new(config);
+  let new_text = match inner_format(&parsed_source, config, &print_options, external_formatter, None, Some(offset_anchors.clone()))? {
+    Some(new_text) => new_text,
+    None if bom_len > 0 => parsed_source.text().to_string(),
+    None => return Ok(None),
+  };
+  Ok(Some(FormatTextWithOffsetMapResult {
+    offset_map: offset_anchors.to_offset_map(bom_len, parsed_source.text(), &new_text, config.indent_width),
+    text: new_text,
+  }))
+}
+
+/// Formats a file and provides the minimal edits to apply to the original text
+/// instead of the whole formatted text.
+///
+/// The edits are sorted, do not overlap, and have ranges relative to the original text.
+/// Returns an empty collection when the file text did not change.
+///
+/// The offsets of Glimmer templates can't be mapped, so Glimmer files get a single
+/// edit that replaces the whole text.
+pub fn format_text_edits(options: FormatTextOptions) -> Result<Vec<TextEdit>> {
+  let original_text = options.text.clone();
+  if get_glimmer_script_extension(options.path, options.extension).is_some() {
+    return Ok(
+      format_text(options)?
+        .map(|new_text| TextEdit {
+          range: 0..original_text.len(),
+          new_text,
+        })
+        .into_iter()
+        .collect(),
+    );
+  }
+  match format_text_with_offset_map(options)? {
+    Some(result) => Ok(get_text_edits(&original_text, &result.text, &result.offset_map)),
+    None => Ok(Vec::new()),
+  }
+}
+
+pub struct FormatTextLenientResult {
+  /// The formatted file text or `None` when it did not change.
+  pub text: Option<String>,
+  /// Byte ranges of the original text that had syntax errors and were left as-is.
+  pub skipped_ranges: Vec<Range<usize>>,
+}
+
+/// Formats a file that may have syntax errors.
+///
+/// The top-level statements that have syntax errors are left as-is and reported
+/// in the result, while the other top-level statements are formatted.
+pub fn format_text_lenient(options: FormatTextOptions) -> Result<FormatTextLenientResult> {
+  let FormatTextOptions {
+    path: file_path,
+    extension: file_extension,
+    text: file_text,
+    config,
+    external_formatter,
+  } = options;
+  let result = format_text(FormatTextOptions {
+    path: file_path,
+    extension: file_extension,
+    text: file_text.clone(),
+    config,
+    external_formatter,
+  });
+  match result {
+    Ok(text) => {
+      return Ok(FormatTextLenientResult {
+        text,
+        skipped_ranges: Vec::new(),
+      })
+    }
+    Err(err) if matches!(err.downcast_ref::<FormatError>(), Some(FormatError::Syntax(_))) => {}
+    Err(err) => return Err(err),
+  }
+  ensure_not_glimmer_file(file_path, file_extension, "Formatting around syntax errors")?;
+
+  let bom_len = if file_text.starts_with("\u{FEFF}") { 3 } else { 0 };
+  let text = &file_text[bom_len..];
+  let (parsed_source, mut skipped_ranges) = parse_swc_ast_lenient(file_path, file_extension, text)?;
+  let item_ranges = parsed_source.with_view(|program| {
+    let source_start = program.text_info().range().start;
+    get_statements_or_members(program.into())
+      .unwrap_or_default()
+      .iter()
+      .map(|item| item.range().as_byte_range(source_start))
+      .collect::<Vec<_>>()
+  });
+  // skip all the statements that overlap the syntax errors
+  for item_range in &item_ranges {
+    if skipped_ranges.iter().any(|range| ranges_intersect(item_range, range)) {
+      skipped_ranges.push(item_range.clone());
+    }
+  }
+  skipped_ranges = merge_ranges(skipped_ranges);
+
+  let mut new_text = String::with_capacity(text.len());
+  let mut last_end = 0;
+  let mut index = 0;
+  while index < item_ranges.len() {
+    let is_skipped = |range: &Range<usize>| skipped_ranges.iter().any(|skipped| ranges_intersect(range, skipped));
+    if is_skipped(&item_ranges[index]) {
+      index += 1;
+      continue;
+    }
+    let start_index = index;
+    index += 1;
+    while index < item_ranges.len() && !is_skipped(&(item_ranges[index - 1].end..item_ranges[index].end)) {
+      index += 1;
+    }
+    let (range, _) = extend_to_line_start(text, item_ranges[start_index].start..item_ranges[index - 1].end);
+    let result = format_text(FormatTextOptions {
+      path: file_path,
+      extension: file_extension,
+      text: text[range.clone()].to_string(),
+      config,
+      external_formatter,
+    });
+    let formatted_text = match result {
+      Ok(Some(formatted_text)) => formatted_text,
+      Ok(None) => continue,
+      Err(err) if matches!(err.downcast_ref::<FormatError>(), Some(FormatError::Syntax(_))) => {
+        skipped_ranges.push(range);
+        continue;
+      }
+      Err(err) => return Err(err),
+    };
+    new_text.push_str(&text[last_end..range.start]);
+    new_text.push_str(formatted_text.trim_end_matches(['\r', '\n']));
+    last_end = range.end;
+  }
+  new_text.push_str(&text[last_end..]);
+
+  Ok(FormatTextLenientResult {
+    text: if bom_len == 0 && new_text == file_text { None } else { Some(new_text) },
+    skipped_ranges: merge_ranges(skipped_ranges)
+      .into_iter()
+      .map(|range| range.start + bom_len..range.end + bom_len)
+      .collect(),
+  })
+}
+
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+  ranges.sort_by_key(|range| range.start);
+  let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+  for range in ranges {
+    match merged.last_mut() {
+      Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+      _ => merged.push(range),
+    }
+  }
+  merged
+}
+
 /// Formats an already parsed source. This is useful as a performance optimization.
 pub fn format_parsed_source(source: &ParsedSource, config: &Configuration, external_formatter: Option<&ExternalFormatter>) -> Result<Option<String>> {
   if super::utils::file_text_has_ignore_comment(source.text(), &config.ignore_file_comment_text) {
     Ok(None)
   } else {
     ensure_no_specific_syntax_errors(source)?;
-    inner_format(source, config, external_formatter)
+    inner_format(source, config, &ConfigPrintOptions::new(config), external_formatter, None, None)
   }
 }
 
-fn inner_format(parsed_source: &ParsedSource, config: &Configuration, external_formatter: Option<&ExternalFormatter>) -> Result<Option<String>> {
+/// Formats the parsed source. External formatter failures are added to `warnings` when provided and error otherwise.
+fn inner_format(
+  parsed_source: &ParsedSource,
+  config: &Configuration,
+  print_options: &ConfigPrintOptions,
+  external_formatter: Option<&ExternalFormatter>,
+  warnings: Option<&mut Vec<FormatError>>,
+  offset_anchors: Option<OffsetAnchors>,
+) -> Result<Option<String>> {
+  let collect_warnings = warnings.is_some();
+  // used to report where the formatted text isn't stable in the original text
+  let offset_anchors = match offset_anchors {
+    None if config.verify_idempotency => Some(OffsetAnchors::default()),
+    offset_anchors => offset_anchors,
+  };
+  let result = print(parsed_source, config, print_options, external_formatter, warnings, offset_anchors.clone())?;
+  if result == parsed_source.text().as_ref() {
+    Ok(None)
+  } else {
+    if config.verify_ast_equivalence {
+      ensure_ast_equivalence(parsed_source, &result, external_formatter.map(|_| &config.tagged_templates_languages))?;
+    }
+    if config.verify_idempotency {
+      ensure_idempotency(
+        parsed_source,
+        &result,
+        config,
+        print_options,
+        external_formatter,
+        collect_warnings,
+        &offset_anchors.unwrap(),
+      )?;
+    }
+    Ok(Some(result))
+  }
+}
+
+fn print(
+  parsed_source: &ParsedSource,
+  config: &Configuration,
+  print_options: &ConfigPrintOptions,
+  external_formatter: Option<&ExternalFormatter>,
+  warnings: Option<&mut Vec<FormatError>>,
+  offset_anchors: Option<OffsetAnchors>,
+) -> Result<String> {
   let mut maybe_err: Box<Option<anyhow::Error>> = Box::new(None);
   let result = dprint_core::formatting::format(
-    || match generate(parsed_source, config, external_formatter) {
-      Ok(print_items) => print_items,
-      Err(e) => {
-        maybe_err.replace(e);
-        PrintItems::default()
+    || {
+      let (print_items, diagnostics, generate_warnings) = generate_with_diagnostics(parsed_source, config, external_formatter, offset_anchors);
+      match warnings {
+        Some(warnings) => {
+          warnings.extend(diagnostics);
+          warnings.extend(generate_warnings);
+          print_items
+        }
+        None => match diagnostics.into_iter().next() {
+          Some(diagnostic) => {
+            maybe_err.replace(diagnostic.into());
+            PrintItems::default()
+          }
+          None => print_items,
+        },
       }
     },
-    config_to_print_options(parsed_source.text(), config),
+    print_options.for_file(parsed_source.text()),
   );
-  if let Some(e) = maybe_err.take() {
-    return Err(e);
+  match maybe_err.take() {
+    Some(e) => Err(e),
+    None => Ok(result),
   }
-  if result == parsed_source.text().as_ref() {
-    Ok(None)
+}
+
+/// Formats the formatted text again and errors when that changes it.
+///
+/// The error is located at the code in the original text that was printed where the
+/// formatted text first changed.
+fn ensure_idempotency(
+  parsed_source: &ParsedSource,
+  formatted_text: &str,
+  config: &Configuration,
+  print_options: &ConfigPrintOptions,
+  external_formatter: Option<&ExternalFormatter>,
+  collect_warnings: bool,
+  offset_anchors: &OffsetAnchors,
+) -> Result<()> {
+  let formatted_source = reparse_swc_ast(parsed_source, formatted_text.into())?;
+  // the warnings were already collected when formatting the first time
+  let mut warnings = Vec::new();
+  let second_text = print(
+    &formatted_source,
+    config,
+    print_options,
+    external_formatter,
+    collect_warnings.then_some(&mut warnings),
+    None,
+  )?;
+  if second_text == formatted_text {
+    return Ok(());
+  }
+
+  let mut byte_index = 0;
+  let mut second_lines = second_text.split('\n');
+  let mut difference = None;
+  for line in formatted_text.split('\n') {
+    let second_line = second_lines.next();
+    if second_line != Some(line) {
+      let indent_len = line.len() - line.trim_start().len();
+      difference = Some((
+        byte_index + indent_len,
+        format!(
+          "Formatting the formatted text changed `{}` to `{}`. This is a bug in the formatter.",
+          line.trim(),
+          second_line.unwrap_or_default().trim()
+        ),
+      ));
+      break;
+    }
+    byte_index += line.len() + 1;
+  }
+  // otherwise the second text only has additional lines
+  let (formatted_offset, message) = difference.unwrap_or_else(|| {
+    (
+      formatted_text.len(),
+      "Formatting the formatted text added lines to the end. This is a bug in the formatter.".to_string(),
+    )
+  });
+
+  let offset_map = offset_anchors.to_offset_map(0, parsed_source.text(), formatted_text, config.indent_width);
+  let original_offset = offset_map.map_formatted_offset(formatted_offset);
+  let text_info = parsed_source.text_info_lazy();
+  let position = text_info.line_and_column_display(text_info.range().start + original_offset);
+  Err(
+    FormatError::Internal {
+      location: Some(ErrorLocation {
+        specifier: parsed_source.specifier().clone(),
+        line_number: position.line_number,
+        column_number: position.column_number,
+        range: original_offset..original_offset,
+      }),
+      message,
+    }
+    .into(),
+  )
+}
+
+pub struct FormatTextRangeOptions<'a> {
+  pub path: &'a Path,
+  pub extension: Option<&'a str>,
+  pub text: String,
+  /// Byte range in the text that should be formatted.
+  pub range: Range<usize>,
+  pub config: &'a Configuration,
+  pub external_formatter: Option<&'a ExternalFormatter<'a>>,
+}
+
+pub struct FormatTextRangeResult {
+  /// The new file text.
+  pub text: String,
+  /// Byte range of the formatted text within the new file text.
+  pub range: Range<usize>,
+}
+
+/// Formats only the smallest set of statements or class/interface members that
+/// cover the provided byte range. All other text is left as-is.
+///
+/// Only the top-level statements that contain the selection are formatted, so
+/// the rest of the file may have syntax errors, but the range must not overlap
+/// any of them. Import and export declarations are never sorted because that
+/// would move statements in and out of the range.
+///
+/// Returns `None` when the text within the range is already formatted.
+pub fn format_text_range(options: FormatTextRangeOptions) -> Result<Option<FormatTextRangeResult>> {
+  let FormatTextRangeOptions {
+    path: file_path,
+    extension: file_extension,
+    text: file_text,
+    range,
+    config,
+    external_formatter,
+  } = options;
+  ensure_not_glimmer_file(file_path, file_extension, "Formatting a range")?;
+  if super::utils::file_text_has_ignore_comment(&file_text, &config.ignore_file_comment_text) {
+    return Ok(None);
+  }
+
+  let bom_len = if file_text.starts_with("\u{FEFF}") { 3 } else { 0 };
+  let text = &file_text[bom_len..];
+  let range = range.start.saturating_sub(bom_len)..range.end.saturating_sub(bom_len);
+  let (parsed_source, error_ranges) = parse_swc_ast_lenient(file_path, file_extension, text)?;
+  let overlaps_error = |range: &Range<usize>| error_ranges.iter().any(|error_range| ranges_intersect(error_range, range));
+  if overlaps_error(&range) {
+    // report the syntax errors
+    parse_swc_ast(file_path, file_extension, text.into())?;
+  }
+  let Some((top_level_range, selection)) = parsed_source.with_view(|program| {
+    let selection = get_range_selection(program, &range)?;
+    // narrow the formatting down to the top-level statements holding the selection
+    let source_start = program.text_info().range().start;
+    let items = get_statements_or_members(program.into())?;
+    let (first_index, last_index) = match selection.path.first() {
+      Some(index) => (*index, *index),
+      None => (selection.first_index, selection.last_index),
+    };
+    let item_range = items[first_index].start().as_byte_index(source_start)..items[last_index].end().as_byte_index(source_start);
+    let (top_level_range, _) = extend_to_line_start(text, item_range);
+    let selection = RangeSelection {
+      path: selection.path.iter().enumerate().map(|(i, index)| if i == 0 { 0 } else { *index }).collect(),
+      first_index: selection.first_index - if selection.path.is_empty() { first_index } else { 0 },
+      last_index: selection.last_index - if selection.path.is_empty() { first_index } else { 0 },
+      is_line_start: selection.is_line_start,
+      range: selection.range.start - top_level_range.start..selection.range.end - top_level_range.start,
+    };
+    Some((top_level_range, selection))
+  }) else {
+    return Ok(None);
+  };
+  if overlaps_error(&top_level_range) {
+    parse_swc_ast(file_path, file_extension, text.into())?;
+  }
+
+  // sorting would move statements outside the range, so maintain the order
+  let mut config = config.clone();
+  config.module_sort_import_declarations = SortOrder::Maintain;
+  config.module_sort_export_declarations = SortOrder::Maintain;
+  let top_level_text = &text[top_level_range.clone()];
+  let top_level_source = parse_swc_ast(file_path, file_extension, top_level_text.into())?;
+  let Some(formatted_text) = inner_format(&top_level_source, &config, &ConfigPrintOptions::new(&config), external_formatter, None, None)? else {
+    return Ok(None);
+  };
+  let formatted_source = parse_swc_ast(file_path, file_extension, formatted_text.into())?;
+  let Some(formatted_range) = formatted_source.with_view(|program| resolve_range_selection(program, &selection)) else {
+    return Err(
+      FormatError::Internal {
+        location: None,
+        message: format!(
+          "Could not find the formatted statements for range {}..{}. This is a bug in the formatter.",
+          bom_len + top_level_range.start + selection.range.start,
+          bom_len + top_level_range.start + selection.range.end
+        ),
+      }
+      .into(),
+    );
+  };
+
+  let new_range_text = &formatted_source.text()[formatted_range];
+  if new_range_text == &top_level_text[selection.range.clone()] {
+    return Ok(None);
+  }
+
+  let new_range_start = bom_len + top_level_range.start + selection.range.start;
+  let new_range_end = bom_len + top_level_range.start + selection.range.end;
+  let mut new_text = String::with_capacity(file_text.len() - selection.range.len() + new_range_text.len());
+  new_text.push_str(&file_text[..new_range_start]);
+  new_text.push_str(new_range_text);
+  new_text.push_str(&file_text[new_range_end..]);
+  Ok(Some(FormatTextRangeResult {
+    text: new_text,
+    range: new_range_start..new_range_start + new_range_text.len(),
+  }))
+}
+
+struct RangeSelection {
+  /// Child indexes from the program to the node that holds the statements or members.
+  path: Vec<usize>,
+  first_index: usize,
+  last_index: usize,
+  /// Whether the range was extended to the start of the first node's line.
+  is_line_start: bool,
+  range: Range<usize>,
+}
+
+fn get_range_selection(program: Program, range: &Range<usize>) -> Option<RangeSelection> {
+  let text_info = program.text_info();
+  let source_start = text_info.range().start;
+  let mut selection = None;
+  let mut path = Vec::new();
+  let mut node: Node = program.into();
+  loop {
+    if let Some(items) = get_statements_or_members(node) {
+      let mut indexes = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| ranges_intersect(&item.range().as_byte_range(source_start), range))
+        .map(|(i, _)| i);
+      if let Some(first_index) = indexes.next() {
+        let last_index = indexes.last().unwrap_or(first_index);
+        let byte_range = items[first_index].start().as_byte_index(source_start)..items[last_index].end().as_byte_index(source_start);
+        let (byte_range, is_line_start) = extend_to_line_start(text_info.text_str(), byte_range);
+        selection = Some(RangeSelection {
+          path: path.clone(),
+          first_index,
+          last_index,
+          is_line_start,
+          range: byte_range,
+        });
+        if first_index != last_index {
+          break;
+        }
+      }
+    }
+
+    // descend into the child that fully contains the range
+    let children = get_children(node);
+    let child_index = children.iter().position(|child| {
+      let child_range = child.range().as_byte_range(source_start);
+      child_range.start <= range.start && range.end <= child_range.end
+    });
+    match child_index {
+      Some(child_index) => {
+        path.push(child_index);
+        node = children[child_index];
+      }
+      None => break,
+    }
+  }
+  selection
+}
+
+fn resolve_range_selection(program: Program, selection: &RangeSelection) -> Option<Range<usize>> {
+  let text_info = program.text_info();
+  let source_start = text_info.range().start;
+  let mut node: Node = program.into();
+  for index in &selection.path {
+    node = *get_children(node).get(*index)?;
+  }
+  let items = get_statements_or_members(node)?;
+  let first = items.get(selection.first_index)?;
+  let last = items.get(selection.last_index)?;
+  let byte_range = first.start().as_byte_index(source_start)..last.end().as_byte_index(source_start);
+  if selection.is_line_start {
+    Some(extend_to_line_start(text_info.text_str(), byte_range).0)
   } else {
-    Ok(Some(result))
+    Some(byte_range)
+  }
+}
+
+fn get_statements_or_members(node: Node) -> Option<Vec<Node>> {
+  match node {
+    Node::Module(node) => Some(node.body.iter().map(|n| n.into()).collect()),
+    Node::Script(node) => Some(node.body.iter().map(|n| n.into()).collect()),
+    Node::BlockStmt(node) => Some(node.stmts.iter().map(|n| n.into()).collect()),
+    Node::SwitchCase(node) => Some(node.cons.iter().map(|n| n.into()).collect()),
+    Node::Class(node) => Some(node.body.iter().map(|n| n.into()).collect()),
+    Node::TsInterfaceBody(node) => Some(node.body.iter().map(|n| n.into()).collect()),
+    Node::TsModuleBlock(node) => Some(node.body.iter().map(|n| n.into()).collect()),
+    _ => None,
+  }
+}
+
+/// Gets the children of a node, looking through parenthesized expressions
+/// since the formatter may add or remove those.
+fn get_children(node: Node) -> Vec<Node> {
+  node
+    .children()
+    .into_iter()
+    .map(|mut child| {
+      while let Node::ParenExpr(paren_expr) = child {
+        child = paren_expr.expr.into();
+      }
+      child
+    })
+    .collect()
+}
+
+fn ranges_intersect(node_range: &Range<usize>, range: &Range<usize>) -> bool {
+  if range.is_empty() {
+    node_range.start <= range.start && range.start <= node_range.end
+  } else {
+    node_range.start < range.end && range.start < node_range.end
+  }
+}
+
+fn extend_to_line_start(text: &str, range: Range<usize>) -> (Range<usize>, bool) {
+  let line_start = text[..range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+  if text[line_start..range.start].chars().all(|c| c == ' ' || c == '\t') {
+    (line_start..range.end, true)
+  } else {
+    (range, false)
   }
 }
 
@@ -118,15 +729,43 @@ fn inner_format(parsed_source: &ParsedSource, config: &Configuration, external_f
 pub fn trace_file(file_path: &Path, file_text: &str, config: &Configuration) -> dprint_core::formatting::TracingResult {
   let parsed_source = parse_swc_ast(file_path, None, file_text.into()).unwrap();
   ensure_no_specific_syntax_errors(&parsed_source).unwrap();
-  dprint_core::formatting::trace_printing(|| generate(&parsed_source, config, None), config_to_print_options(file_text, config))
+  dprint_core::formatting::trace_printing(
+    || super::generation::generate(&parsed_source, config, None).unwrap(),
+    ConfigPrintOptions::new(config).for_file(file_text),
+  )
 }
 
-fn config_to_print_options(file_text: &str, config: &Configuration) -> PrintOptions {
-  PrintOptions {
-    indent_width: config.indent_width,
-    max_width: config.line_width,
-    use_tabs: config.use_tabs,
-    new_line_text: resolve_new_line_kind(file_text, config.new_line_kind),
+/// The print options of a configuration, which only depend on the file text
+/// when the newline kind is detected from it.
+#[derive(Clone)]
+pub(crate) struct ConfigPrintOptions {
+  indent_width: u8,
+  max_width: u32,
+  use_tabs: bool,
+  /// `None` when the newline kind is `auto`.
+  new_line_text: Option<&'static str>,
+}
+
+impl ConfigPrintOptions {
+  pub fn new(config: &Configuration) -> Self {
+    Self {
+      indent_width: config.indent_width,
+      max_width: config.line_width,
+      use_tabs: config.use_tabs,
+      new_line_text: match config.new_line_kind {
+        NewLineKind::Auto => None,
+        new_line_kind => Some(resolve_new_line_kind("", new_line_kind)),
+      },
+    }
+  }
+
+  pub fn for_file(&self, file_text: &str) -> PrintOptions {
+    PrintOptions {
+      indent_width: self.indent_width,
+      max_width: self.max_width,
+      use_tabs: self.use_tabs,
+      new_line_text: self.new_line_text.unwrap_or_else(|| resolve_new_line_kind(file_text, NewLineKind::Auto)),
+    }
   }
 }
 
@@ -151,6 +790,447 @@ mod test {
     }
   }
 
+  #[test]
+  fn formats_range() {
+    fn run_test(input_text: &str, range: Range<usize>, expected: Option<(&str, Range<usize>)>) {
+      let config = crate::configuration::ConfigurationBuilder::new().build();
+      let result = format_text_range(FormatTextRangeOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: input_text.into(),
+        range,
+        config: &config,
+        external_formatter: None,
+      })
+      .unwrap();
+      assert_eq!(result.map(|r| (r.text, r.range)), expected.map(|(text, range)| (text.to_string(), range)));
+    }
+
+    // single statement
+    run_test(
+      "const  a  =  1;\nconst  b  =  2;\nconst  c  =  3;\n",
+      18..19,
+      Some(("const  a  =  1;\nconst b = 2;\nconst  c  =  3;\n", 16..28)),
+    );
+    // multiple statements
+    run_test(
+      "const  a  =  1;\nconst  b  =  2;\nconst  c  =  3;\n",
+      5..20,
+      Some(("const a = 1;\nconst b = 2;\nconst  c  =  3;\n", 0..25)),
+    );
+    // class member with bad indentation
+    run_test(
+      "class  A {\n      a  =  1;\n  b  =  2;\n}\n",
+      17..18,
+      Some(("class  A {\n  a = 1;\n  b  =  2;\n}\n", 11..19)),
+    );
+    // cursor position
+    run_test("let  a;\nlet  b;\n", 8..8, Some(("let  a;\nlet b;\n", 8..14)));
+    // between statements
+    run_test("let  a;\n\n\nlet  b;\n", 8..9, None);
+    // already formatted
+    run_test("let a;\nlet  b;\n", 0..6, None);
+    // nested in a later top-level statement
+    run_test(
+      "let  a;\nfunction  f() {\n      let  b;\n  let  c;\n}\n",
+      30..31,
+      Some(("let  a;\nfunction  f() {\n  let b;\n  let  c;\n}\n", 24..32)),
+    );
+    // syntax error elsewhere in the file
+    run_test(
+      "const  a  =  1;\nconst b = ;\nclass  A {\n      c  =  1;\n}\nconst  d = ;\n",
+      0..5,
+      Some(("const a = 1;\nconst b = ;\nclass  A {\n      c  =  1;\n}\nconst  d = ;\n", 0..12)),
+    );
+    run_test(
+      "const  a  =  1;\nconst b = ;\nclass  A {\n      c  =  1;\n}\nconst  d = ;\n",
+      46..47,
+      Some(("const  a  =  1;\nconst b = ;\nclass  A {\n  c = 1;\n}\nconst  d = ;\n", 39..47)),
+    );
+  }
+
+  #[test]
+  fn formats_range_overlapping_syntax_error() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let result = format_text_range(FormatTextRangeOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: "const  a  =  1;\nconst b = ;\n".into(),
+      range: 0..20,
+      config: &config,
+      external_formatter: None,
+    });
+    assert!(matches!(result.err().unwrap().downcast_ref::<FormatError>(), Some(FormatError::Syntax(_))));
+  }
+
+  #[test]
+  fn maps_offsets() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let input_text = "\u{FEFF}let  a  =  'b' ;\nif(a){  call( a ) }//c\n";
+    let result = format_text_with_offset_map(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: input_text.into(),
+      config: &config,
+      external_formatter: None,
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(result.text, "let a = \"b\";\nif (a) call(a); // c\n");
+    let map_text = |text: &str| {
+      let offset = input_text.find(text).unwrap();
+      &result.text[result.offset_map.map_range(offset..offset + text.len()).unwrap()]
+    };
+    assert_eq!(map_text("a  ="), "a =");
+    assert_eq!(map_text("'b'"), "\"b\"");
+    assert_eq!(map_text("if(a)"), "if (a)");
+    assert_eq!(map_text("call( a )"), "call(a)");
+    assert_eq!(map_text("//c"), "// c");
+    assert_eq!(result.offset_map.map_offset(0), Some(0));
+    assert_eq!(result.offset_map.map_offset(input_text.len()), Some(result.text.len()));
+    assert_eq!(result.offset_map.map_offset(input_text.len() + 1), None);
+  }
+
+  #[test]
+  fn maps_offsets_in_sorted_code() {
+    let config = crate::configuration::ConfigurationBuilder::new()
+      .module_sort_import_declarations(SortOrder::CaseInsensitive)
+      .import_declaration_sort_named_imports(SortOrder::CaseInsensitive)
+      .build();
+    let input_text = "import { cc, bb } from \"./b\";\n\timport { aa } from \"./a\";\n\n// c\nconsole.log(aa, bb, cc);\n";
+    let result = format_text_with_offset_map(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: input_text.into(),
+      config: &config,
+      external_formatter: None,
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+      result.text,
+      "import { aa } from \"./a\";\nimport { bb, cc } from \"./b\";\n\n// c\nconsole.log(aa, bb, cc);\n"
+    );
+    let map_offset = |text: &str, index: usize| {
+      let offset = input_text.find(text).unwrap() + index;
+      result.offset_map.map_offset(offset).map(|offset| &result.text[offset..])
+    };
+    // cursor inside a reordered named import and import declaration
+    assert_eq!(map_offset("cc,", 1).unwrap(), &result.text[result.text.find("c }").unwrap()..]);
+    assert_eq!(map_offset("./a", 1).unwrap(), &result.text[result.text.find("/a").unwrap()..]);
+    assert_eq!(map_offset("import { aa", 0).unwrap(), result.text);
+    assert_eq!(map_offset("./b\"", 3).unwrap(), &result.text[result.text.find("\";\n\n").unwrap()..]);
+    assert_eq!(map_offset("// c", 3).unwrap(), &result.text[result.text.find("c\nconsole").unwrap()..]);
+    // whitespace between the reordered code has no mapping
+    assert_eq!(map_offset(", bb", 1), None);
+    assert_eq!(map_offset("\timport", 0), None);
+  }
+
+  #[test]
+  fn gets_text_edits() {
+    fn run_test(input_text: &str, expected: Vec<(Range<usize>, &str)>) {
+      let config = crate::configuration::ConfigurationBuilder::new().build();
+      let edits = format_text_edits(FormatTextOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: input_text.into(),
+        config: &config,
+        external_formatter: None,
+      })
+      .unwrap();
+      assert_eq!(edits.iter().map(|e| (e.range.clone(), e.new_text.as_str())).collect::<Vec<_>>(), expected);
+    }
+
+    run_test("let a = 1;\n", Vec::new());
+    run_test(
+      "let  a = 'b'\n//c\ncall( a )\n",
+      vec![(4..5, ""), (9..12, "\"b\";"), (15..15, " "), (22..23, ""), (24..25, ""), (26..26, ";")],
+    );
+    run_test("\u{FEFF}let a = 1;\n", vec![(0..3, "")]);
+    run_test("let a = \"ü\"  ;", vec![(12..14, ""), (15..15, "\n")]);
+  }
+
+  #[test]
+  fn formats_lenient() {
+    fn run_test(input_text: &str, expected_text: Option<&str>, expected_skipped: Vec<&str>) {
+      let config = crate::configuration::ConfigurationBuilder::new().build();
+      let result = format_text_lenient(FormatTextOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: input_text.into(),
+        config: &config,
+        external_formatter: None,
+      })
+      .unwrap();
+      assert_eq!(result.text.as_deref(), expected_text);
+      assert_eq!(result.skipped_ranges.into_iter().map(|r| &input_text[r]).collect::<Vec<_>>(), expected_skipped);
+    }
+
+    // no syntax errors
+    run_test("let  a = 1;", Some("let a = 1;\n"), Vec::new());
+    // fatal syntax error
+    run_test(
+      "let  a = 1;\nconst a = ;\nlet  b = 2;\n",
+      Some("let a = 1;\nconst a = ;\nlet b = 2;\n"),
+      vec!["const a = ;"],
+    );
+    // unclosed paren
+    run_test(
+      "let  a = 1;\nfoo(\nlet  b = 2;\n",
+      Some("let a = 1;\nfoo(\nlet  b = 2;\n"),
+      vec!["foo(\nlet  b = 2;"],
+    );
+    // non-fatal syntax error
+    run_test(
+      "let  a = 1;\nconst Methods {\nf: 1,\n};\nlet  b=2;",
+      Some("let a = 1;\nconst Methods {\nf: 1,\n};\nlet b = 2;"),
+      vec!["const Methods {\nf: 1,\n};"],
+    );
+    // multiple errors
+    run_test(
+      "class  A {\n  a( {\n}\n}\nlet  a = 1;\nconst b = ;\n",
+      None,
+      vec!["class  A {\n  a( {\n}\n}\nlet  a = 1;\nconst b = ;"],
+    );
+  }
+
+  #[test]
+  fn verifies_idempotency() {
+    let config = crate::configuration::ConfigurationBuilder::new().verify_idempotency(true).build();
+    let format = |text: &str| {
+      format_text(FormatTextOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: text.into(),
+        config: &config,
+        external_formatter: Some(&|request| Ok(Some(format!("{}!", request.text.trim())))),
+      })
+    };
+    assert_eq!(format("let  a = 1;").unwrap().unwrap(), "let a = 1;\n");
+    let err = format("let  a = 1;\nconst b = css`a`;").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Formatting the formatted text changed `a!` to `a!!`. This is a bug in the formatter.\n    at file:///test.ts:2:15"
+    );
+    // the location is of the original text
+    match err.downcast_ref::<FormatError>().unwrap() {
+      FormatError::Internal { location: Some(location), .. } => assert_eq!(location.range, 26..26),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn external_formatter_request() {
+    let config = crate::configuration::ConfigurationBuilder::new().line_width(40).build();
+    let requests = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let external_formatter = {
+      let requests = requests.clone();
+      move |request: ExternalFormatRequest| {
+        requests.borrow_mut().push((
+          request.tag_name.to_string(),
+          request.start_column,
+          request.indent_column,
+          request.line_width,
+          request.placeholders.clone(),
+        ));
+        Ok(Some(request.text))
+      }
+    };
+    let result = format_text(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: "function f() {\n  const styles = css`color: ${color};`;\n\tconst Div = styled.div`a: ${b}; c: ${d};`;\n}\n".into(),
+      config: &config,
+      external_formatter: Some(&external_formatter),
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+      result,
+      "function f() {\n  const styles = css`\n    color: ${color};\n  `;\n  const Div = styled.div`\n    a: ${b}; c: ${d};\n  `;\n}\n"
+    );
+    assert_eq!(
+      *requests.borrow(),
+      vec![
+        ("css".to_string(), 17, 4, 36, vec!["@dpr1nt_00_d".to_string()]),
+        (
+          "styled.div".to_string(),
+          14,
+          4,
+          36,
+          vec!["@dpr1nt_00_d".to_string(), "@dpr1nt_01_d".to_string()]
+        ),
+      ]
+    );
+
+    // the columns are of the printed code rather than the original indentation
+    requests.borrow_mut().clear();
+    for text in [
+      "class A {\nm() {\n          if (a) {\n    const  b =  css`a: b;`;\n}\n}\n}\n",
+      "class A{m(){if(a){const b=css`a: b;`}}}",
+    ] {
+      format_text(FormatTextOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: text.into(),
+        config: &config,
+        external_formatter: Some(&external_formatter),
+      })
+      .unwrap();
+    }
+    assert_eq!(
+      requests.borrow().iter().map(|request| (request.1, request.2, request.3)).collect::<Vec<_>>(),
+      // the text before the template on its line is measured as written (`const b=`)
+      vec![(16, 8, 32), (14, 8, 32)]
+    );
+  }
+
+  #[test]
+  fn tagged_templates_languages() {
+    let config = crate::configuration::ConfigurationBuilder::new()
+      .tagged_templates_languages([("dbQuery", "sql"), ("keyframes", "css"), ("gql", "none")])
+      .build();
+    let external_formatter = |request: ExternalFormatRequest| {
+      Ok(match request.language {
+        "none" => None,
+        language => Some(format!("{} {:?}", language, request.media_type)),
+      })
+    };
+    let result = format_text(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: "dbQuery`a`;\nkeyframes`a`;\nstyled.div.attrs({})`a`;\nstyled(Button)`a`;\ngql`a`;\nother`a`;\n".into(),
+      config: &config,
+      external_formatter: Some(&external_formatter),
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+      result,
+      "dbQuery`\n  sql Sql\n`;\nkeyframes`\n  css Css\n`;\nstyled.div.attrs({})`\n  css Css\n`;\nstyled(Button)`\n  css Css\n`;\ngql`a`;\nother`a`;\n"
+    );
+  }
+
+  #[test]
+  fn comment_hinted_templates() {
+    let config = crate::configuration::ConfigurationBuilder::new().verify_ast_equivalence(true).build();
+    let external_formatter = |request: ExternalFormatRequest| {
+      assert_eq!(request.tag_name, "");
+      Ok(Some(format!("{} {}", request.language, request.text.trim().replace("select", "SELECT"))))
+    };
+    let result = format_text(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: "const q = /* sql */ `select ${a}`;\nconst g = /* GraphQL */`query`;\nconst o = /* other */ `a`;\nconst l = // sql\n  `a`;\n".into(),
+      config: &config,
+      external_formatter: Some(&external_formatter),
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+      result,
+      "const q = /* sql */ `\n  sql SELECT ${a}\n`;\nconst g = /* GraphQL */ `\n  graphql query\n`;\nconst o = /* other */ `a`;\nconst l = // sql\n  `a`;\n"
+    );
+  }
+
+  #[test]
+  fn angular_component_templates() {
+    let config = crate::configuration::ConfigurationBuilder::new().verify_ast_equivalence(true).build();
+    let external_formatter = |request: ExternalFormatRequest| Ok(Some(format!("{} {}", request.language, request.text.trim())));
+    let result = format_text(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: concat!(
+        "@Component({\n",
+        "  selector: `app`,\n",
+        "  template: `<p>{{ ${a} }}</p>`,\n",
+        "  styles: [`p { color: red; }`, `a {}`],\n",
+        "})\n",
+        "class A {}\n",
+        "@Other({ template: `a` })\n",
+        "class B {}\n",
+        "@Component({ 'styles': `a {}` })\n",
+        "class C {}\n",
+      )
+      .into(),
+      config: &config,
+      external_formatter: Some(&external_formatter),
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+      result,
+      concat!(
+        "@Component({\n",
+        "  selector: `app`,\n",
+        "  template: `\n    html <p>{{ ${a} }}</p>\n  `,\n",
+        "  styles: [\n    `\n      css p { color: red; }\n    `,\n    `\n      css a {}\n    `,\n  ],\n",
+        "})\n",
+        "class A {}\n",
+        "@Other({ template: `a` })\n",
+        "class B {}\n",
+        "@Component({\n  \"styles\": `\n    css a {}\n  `,\n})\n",
+        "class C {}\n",
+      )
+    );
+  }
+
+  #[test]
+  fn graphql_placeholders() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let texts = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let external_formatter = {
+      let texts = texts.clone();
+      move |request: ExternalFormatRequest| {
+        assert_eq!(request.language, "graphql");
+        texts.borrow_mut().push(request.text.clone());
+        Ok(Some(request.text.lines().map(|line| line.trim()).collect::<Vec<_>>().join("\n")))
+      }
+    };
+    let format = |text: &str| {
+      format_text(FormatTextOptions {
+        path: &std::path::PathBuf::from("test.ts"),
+        extension: None,
+        text: text.into(),
+        config: &config,
+        external_formatter: Some(&external_formatter),
+      })
+    };
+    assert_eq!(
+      format("const q = gql`query Q($id: ID) { user(id: ${id}) { ...${frag} } } ${fragment} query B { b }`;")
+        .unwrap()
+        .unwrap(),
+      "const q = gql`\n  query Q($id: ID) { user(id: ${id}) { ...${frag} } } ${fragment}\n  query B { b }\n`;\n"
+    );
+    assert_eq!(
+      texts.borrow()[0],
+      "query Q($id: ID) { user(id: dpr1nt_00_d) { ...dpr1nt_01_d } } #dpr1nt_02_d\n query B { b }"
+    );
+    // an escaped quote doesn't end the string
+    format(r#"const q = gql`{ a(b: "\\"}") } ${fragment}`;"#).unwrap();
+    assert_eq!(texts.borrow()[1], "{ a(b: \"\\\"}\") } #dpr1nt_00_d\n");
+
+    // leaves the template alone when a placeholder is removed and warns about it
+    let path = std::path::PathBuf::from("test.ts");
+    let options = || FormatTextOptions {
+      path: &path,
+      extension: None,
+      text: "graphql`query { a }\n${a}`;".into(),
+      config: &config,
+      external_formatter: Some(&|request| Ok(Some(request.text.lines().filter(|l| !l.starts_with('#')).collect::<Vec<_>>().join("\n")))),
+    };
+    assert_eq!(format_text(options()).unwrap().unwrap(), "graphql`query { a }\n${a}`;\n");
+    let result = format_text_with_warnings(options()).unwrap();
+    assert_eq!(result.text.unwrap(), "graphql`query { a }\n${a}`;\n");
+    assert_eq!(
+      result.warnings.iter().map(|warning| warning.to_string()).collect::<Vec<_>>(),
+      vec![
+        "Error formatting tagged template literal at line 0: The formatted text did not contain the placeholders for the expressions in order, so the template was left as-is. Missing placeholder: #dpr1nt_00_d"
+          .to_string()
+      ]
+    );
+  }
+
   #[test]
   fn syntax_error_from_external_formatter() {
     let config = crate::configuration::ConfigurationBuilder::new().build();
@@ -159,15 +1239,114 @@ mod test {
       extension: None,
       text: "const content = html`<div>broken html</p>`".into(),
       config: &config,
-      external_formatter: Some(&|media_type, _text, _config| {
-        assert!(matches!(media_type, deno_ast::MediaType::Html));
+      external_formatter: Some(&|request| {
+        assert!(matches!(request.media_type, deno_ast::MediaType::Html));
         Err(anyhow::anyhow!("Syntax error from external formatter"))
       }),
     });
     assert!(result.is_err());
+    let err = result.unwrap_err();
     assert_eq!(
-      result.unwrap_err().to_string(),
+      err.to_string(),
       "Error formatting tagged template literal at line 0: Syntax error from external formatter"
     );
+    match err.downcast_ref::<FormatError>().unwrap() {
+      FormatError::ExternalFormatter { location, message } => {
+        assert_eq!(location.line_number, 1);
+        assert_eq!(location.column_number, 17);
+        assert_eq!(location.range, 16..42);
+        assert_eq!(location.file_path().file_name().unwrap(), "test.ts");
+        assert_eq!(message, "Syntax error from external formatter");
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn warnings_from_external_formatter() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let result = format_text_with_warnings(FormatTextOptions {
+      path: &std::path::PathBuf::from("test.ts"),
+      extension: None,
+      text: "const a = html`<div>`;\nconst  b = css`a`;\nconst c = html`</p>`;\n".into(),
+      config: &config,
+      external_formatter: Some(&|request| match request.media_type {
+        deno_ast::MediaType::Html => Err(anyhow::anyhow!("Unclosed element")),
+        _ => Ok(Some(request.text)),
+      }),
+    })
+    .unwrap();
+    assert_eq!(result.text.unwrap(), "const a = html`<div>`;\nconst b = css`\n  a\n`;\nconst c = html`</p>`;\n");
+    assert_eq!(
+      result
+        .warnings
+        .iter()
+        .map(|warning| (warning.location().unwrap().line_number, warning.to_string()))
+        .collect::<Vec<_>>(),
+      vec![
+        (1, "Error formatting tagged template literal at line 0: Unclosed element".to_string()),
+        (3, "Error formatting tagged template literal at line 2: Unclosed element".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn formats_glimmer_files() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let format = |path: &str, text: &str| {
+      format_text(FormatTextOptions {
+        path: &std::path::PathBuf::from(path),
+        extension: None,
+        text: text.into(),
+        config: &config,
+        external_formatter: None,
+      })
+      .unwrap()
+    };
+    assert_eq!(
+      format(
+        "component.gts",
+        "import  Component from '@glimmer/component';\nconst  Greeting = <template>  <p>Hi</p></template>\nexport default class  Hello extends Component<{a:string}> {\n<template>\n  <Greeting />  {{@a}}\n</template>\n  get a(){return 1}\n}\n",
+      )
+      .unwrap(),
+      "import Component from \"@glimmer/component\";\nconst Greeting = <template>  <p>Hi</p></template>;\nexport default class Hello extends Component<{ a: string }> {\n  <template>\n    <Greeting />  {{@a}}\n  </template>\n  get a() {\n    return 1;\n  }\n}\n",
+    );
+    assert_eq!(format("component.gjs", "<template>\n  Hi\n</template>\n"), None);
+  }
+
+  #[test]
+  fn glimmer_files_without_offsets() {
+    let config = crate::configuration::ConfigurationBuilder::new().build();
+    let path = std::path::PathBuf::from("component.gts");
+    let text = "const  a = <template>Hi</template>;\n";
+    let options = || FormatTextOptions {
+      path: &path,
+      extension: None,
+      text: text.into(),
+      config: &config,
+      external_formatter: None,
+    };
+    let edits = format_text_edits(options()).unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].range, 0..text.len());
+    assert_eq!(edits[0].new_text, "const a = <template>Hi</template>;\n");
+    assert_eq!(
+      format_text_with_offset_map(options()).err().unwrap().to_string(),
+      "Mapping offsets is not supported for Glimmer files (.gts and .gjs): component.gts"
+    );
+    assert_eq!(
+      format_text_range(FormatTextRangeOptions {
+        path: &path,
+        extension: None,
+        text: text.into(),
+        range: 0..5,
+        config: &config,
+        external_formatter: None,
+      })
+      .err()
+      .unwrap()
+      .to_string(),
+      "Formatting a range is not supported for Glimmer files (.gts and .gjs): component.gts"
+    );
   }
 }
@@ -0,0 +1,203 @@
+use std::num::NonZeroUsize;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Result;
+
+use super::configuration::Configuration;
+use super::error::FormatError;
+use super::format_text::format_text_collecting_warnings;
+use super::format_text::ConfigPrintOptions;
+use super::format_text::FormatTextOptions;
+use super::generation::ExternalFormatRequest;
+use super::utils::get_panic_message;
+
+type SyncExternalFormatter = dyn Fn(ExternalFormatRequest) -> Result<Option<String>> + Send + Sync;
+
+/// Formats many files with the same configuration.
+///
+/// # Example
+///
+/// ```
+/// use std::path::PathBuf;
+/// use dprint_plugin_typescript::*;
+/// use dprint_plugin_typescript::configuration::*;
+///
+/// let formatter = Formatter::new(ConfigurationBuilder::new().line_width(80).build());
+/// let results = formatter.format_many(vec![
+///     (PathBuf::from("path/to/file.ts"), "const  t  =  5 ;".to_string()),
+///     (PathBuf::from("path/to/other.ts"), "const t = 5;\n".to_string()),
+/// ]);
+/// assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("const t = 5;\n"));
+/// assert_eq!(results[1].as_ref().unwrap().as_deref(), None);
+/// ```
+#[derive(Clone)]
+pub struct Formatter {
+  config: Configuration,
+  print_options: ConfigPrintOptions,
+  external_formatter: Option<Arc<SyncExternalFormatter>>,
+  thread_count: NonZeroUsize,
+}
+
+impl Formatter {
+  pub fn new(config: Configuration) -> Self {
+    Self {
+      print_options: ConfigPrintOptions::new(&config),
+      config,
+      external_formatter: None,
+      thread_count: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+    }
+  }
+
+  /// Sets the formatter to use for embedded languages. See `ExternalFormatter`.
+  pub fn with_external_formatter(mut self, external_formatter: impl Fn(ExternalFormatRequest) -> Result<Option<String>> + Send + Sync + 'static) -> Self {
+    self.external_formatter = Some(Arc::new(external_formatter));
+    self
+  }
+
+  /// Sets the maximum number of threads `format_many` uses.
+  ///
+  /// Default: The available parallelism of the system.
+  pub fn with_thread_count(mut self, thread_count: NonZeroUsize) -> Self {
+    self.thread_count = thread_count;
+    self
+  }
+
+  pub fn config(&self) -> &Configuration {
+    &self.config
+  }
+
+  /// Formats a file. See `format_text`.
+  pub fn format_text(&self, path: &Path, text: String) -> Result<Option<String>> {
+    format_text_collecting_warnings(
+      FormatTextOptions {
+        path,
+        extension: None,
+        text,
+        config: &self.config,
+        external_formatter: self.external_formatter.as_deref().map(|f| f as _),
+      },
+      None,
+      &self.print_options,
+    )
+  }
+
+  /// Formats a file, reporting a panic as an internal error.
+  fn format_text_catching_panic(&self, path: &Path, text: String) -> Result<Option<String>> {
+    match catch_unwind(AssertUnwindSafe(|| self.format_text(path, text))) {
+      Ok(result) => result,
+      Err(panic) => Err(
+        FormatError::Internal {
+          location: None,
+          message: format!("Formatter panicked: {}", get_panic_message(panic.as_ref())),
+        }
+        .into(),
+      ),
+    }
+  }
+
+  /// Formats the files in parallel.
+  ///
+  /// Returns the result of each file in the same order as the provided files.
+  /// A panic while formatting a file is returned as that file's internal error.
+  pub fn format_many(&self, files: Vec<(PathBuf, String)>) -> Vec<Result<Option<String>>> {
+    let file_count = files.len();
+    let thread_count = self.thread_count.get().min(file_count);
+    if thread_count <= 1 {
+      return files.into_iter().map(|(path, text)| self.format_text_catching_panic(&path, text)).collect();
+    }
+
+    let files = Mutex::new(files.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+      for _ in 0..thread_count {
+        let files = &files;
+        let sender = sender.clone();
+        scope.spawn(move || loop {
+          let Some((index, (path, text))) = files.lock().unwrap().next() else {
+            break;
+          };
+          sender.send((index, self.format_text_catching_panic(&path, text))).unwrap();
+        });
+      }
+    });
+    drop(sender);
+
+    let mut results = receiver.into_iter().collect::<Vec<_>>();
+    debug_assert_eq!(results.len(), file_count);
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::configuration::ConfigurationBuilder;
+
+  #[test]
+  fn formats_many() {
+    let formatter = Formatter::new(ConfigurationBuilder::new().build())
+      .with_thread_count(NonZeroUsize::new(3).unwrap())
+      .with_external_formatter(|request| Ok(Some(request.text.to_uppercase())));
+    let files = (0..20)
+      .map(|i| {
+        let text = if i == 5 {
+          "const a = ;".to_string()
+        } else {
+          format!("const  a{} = css`a`;", i)
+        };
+        (PathBuf::from(format!("file{}.ts", i)), text)
+      })
+      .collect::<Vec<_>>();
+    let results = formatter.format_many(files);
+    assert_eq!(results.len(), 20);
+    for (i, result) in results.into_iter().enumerate() {
+      if i == 5 {
+        assert!(result.is_err());
+      } else {
+        assert_eq!(result.unwrap().unwrap(), format!("const a{} = css`\n  A\n`;\n", i));
+      }
+    }
+  }
+
+  #[test]
+  fn reports_panic_per_file() {
+    for thread_count in [1, 3] {
+      let formatter = Formatter::new(ConfigurationBuilder::new().build())
+        .with_thread_count(NonZeroUsize::new(thread_count).unwrap())
+        .with_external_formatter(|request| {
+          if request.text == "panic" {
+            panic!("external formatter panicked");
+          }
+          Ok(None)
+        });
+      let files = (0..5)
+        .map(|i| {
+          let text = if i == 2 { "const a = css`panic`;" } else { "const  a = css`a`;" };
+          (PathBuf::from(format!("file{}.ts", i)), text.to_string())
+        })
+        .collect::<Vec<_>>();
+      let results = formatter.format_many(files);
+      for (i, result) in results.into_iter().enumerate() {
+        if i == 2 {
+          let err = result.unwrap_err();
+          assert_eq!(
+            err.downcast_ref::<FormatError>().unwrap(),
+            &FormatError::Internal {
+              location: None,
+              message: "Formatter panicked: external formatter panicked".to_string(),
+            }
+          );
+        } else {
+          assert_eq!(result.unwrap().unwrap(), "const a = css`a`;\n");
+        }
+      }
+    }
+  }
+}
@@ -2,10 +2,12 @@ use deno_ast::swc::common::comments::Comment;
 use deno_ast::swc::parser::token::TokenAndSpan;
 use deno_ast::view::*;
 use deno_ast::MediaType;
+use deno_ast::ModuleSpecifier;
 use deno_ast::SourcePos;
 use deno_ast::SourceRange;
 use deno_ast::SourceRanged;
 use deno_ast::SourceRangedForSpanned;
+use dprint_core::formatting::Condition;
 use dprint_core::formatting::ConditionReference;
 use dprint_core::formatting::IndentLevel;
 use dprint_core::formatting::IsStartOfLine;
@@ -16,11 +18,18 @@ use rustc_hash::FxHashSet;
 
 use super::*;
 use crate::configuration::*;
+use crate::error::FormatError;
+use crate::offset_map::OffsetAnchors;
 use crate::utils::Stack;
 
 /// A callback that will be called when encountering certain tagged templates.
 ///
-/// Currently supports `css`, `html` and `sql` tagged templated.
+/// By default supports `css`, `graphql`, `html` and `sql` tagged templated. Other tags can be
+/// mapped to a language with the `taggedTemplates.languages` configuration.
+///
+/// It is also called for template literals with a leading block comment that names one of
+/// those languages or tags (ex. ``/* sql */ `SELECT * FROM users` ``) and for the `template` (html)
+/// and `styles` (css) of an Angular component's `@Component({ ... })` decorator.
 ///
 /// Examples:
 /// ```ignore
@@ -41,22 +50,76 @@ use crate::utils::Stack;
 ///   active IS TRUE;
 /// ```
 ///
-/// External formatter should return `None` if it doesn't understand given `MediaType`, in such
+/// External formatter should return `None` if it doesn't understand given `MediaType` or language, in such
 /// cases the templates will be left as they are.
 ///
-/// Only templates with no interpolation are supported.
-pub type ExternalFormatter = dyn Fn(MediaType, String, &Configuration) -> anyhow::Result<Option<String>>;
+/// Expressions in the template (`${...}`) are replaced with the placeholders found in
+/// `ExternalFormatRequest::placeholders`. The external formatter must keep them in its output
+/// or the template will be left as it is. In GraphQL, expressions at the document level are
+/// replaced with comments (ex. `#dpr1nt_00_d`) on their own line.
+///
+/// Use `adapt_legacy_external_formatter` to use a formatter that only receives the media type,
+/// text and configuration.
+pub type ExternalFormatter<'a> = dyn Fn(ExternalFormatRequest) -> anyhow::Result<Option<String>> + 'a;
+
+/// The previous signature of `ExternalFormatter`.
+pub type LegacyExternalFormatter = dyn Fn(MediaType, String, &Configuration) -> anyhow::Result<Option<String>>;
+
+/// Adapts a formatter with the previous `ExternalFormatter` signature.
+///
+/// Languages without a media type are left as they are.
+///
+/// ```
+/// use dprint_plugin_typescript::*;
+///
+/// let external_formatter = adapt_legacy_external_formatter(|_media_type, text, _config| Ok(Some(text)));
+/// let external_formatter: &ExternalFormatter = &external_formatter;
+/// ```
+pub fn adapt_legacy_external_formatter(
+  external_formatter: impl Fn(MediaType, String, &Configuration) -> anyhow::Result<Option<String>>,
+) -> impl Fn(ExternalFormatRequest) -> anyhow::Result<Option<String>> {
+  move |request| match request.media_type {
+    MediaType::Unknown => Ok(None),
+    media_type => external_formatter(media_type, request.text, request.config),
+  }
+}
 
-pub(crate) struct GenerateDiagnostic {
-  pub message: String,
+/// The embedded text to format along with the context it appears in.
+///
+/// The surrounding code has not been printed yet when the external formatter is called, so the
+/// columns are based on the indent level the template's line will be printed at (the file indent
+/// level plus the template's nesting) rather than the original indentation.
+#[derive(Clone)]
+pub struct ExternalFormatRequest<'a> {
+  /// Media type of the language or `MediaType::Unknown` for languages without one (ex. `graphql`).
+  pub media_type: MediaType,
+  /// Language from the `taggedTemplates.languages` configuration (ex. `css`).
+  pub language: &'a str,
+  /// Text of the template with the expressions replaced by placeholders.
+  pub text: String,
+  pub config: &'a Configuration,
+  /// Source text of the template's tag (ex. `css`, `styled.div`, or `styled(Button)`).
+  /// This is empty for template literals without a tag.
+  pub tag_name: &'a str,
+  /// 0-indexed column the tagged template starts at, where a tab is the indent width.
+  ///
+  /// The text before the template on its line is measured as written with its whitespace collapsed.
+  pub start_column: u32,
+  /// Column the lines of the formatted text will be indented to.
+  pub indent_column: u32,
+  /// Width available to the lines of the formatted text (the line width minus the indentation).
+  pub line_width: u32,
+  /// The placeholders in the text, in the order of the expressions they stand for.
+  pub placeholders: Vec<String>,
 }
 
 pub struct Context<'a> {
   pub media_type: MediaType,
+  pub specifier: &'a ModuleSpecifier,
   pub program: Program<'a>,
   pub config: &'a Configuration,
   pub comments: CommentTracker<'a>,
-  pub external_formatter: Option<&'a ExternalFormatter>,
+  pub external_formatter: Option<&'a ExternalFormatter<'a>>,
   pub token_finder: TokenFinder<'a>,
   pub current_node: Node<'a>,
   pub parent_stack: Stack<Node<'a>>,
@@ -74,20 +137,27 @@ pub struct Context<'a> {
   /// Used for ensuring nodes are parsed in order.
   #[cfg(debug_assertions)]
   pub last_generated_node_pos: SourcePos,
-  pub diagnostics: Vec<GenerateDiagnostic>,
+  pub diagnostics: Vec<FormatError>,
+  /// Problems that only fail formatting when collecting warnings (ex. a template the
+  /// external formatter formatted without its placeholders, which is left as-is).
+  pub warnings: Vec<FormatError>,
+  /// Records where nodes and comments are printed when creating an offset map.
+  pub offset_anchors: Option<OffsetAnchors>,
 }
 
 impl<'a> Context<'a> {
   pub fn new(
     media_type: MediaType,
+    specifier: &'a ModuleSpecifier,
     tokens: &'a [TokenAndSpan],
     current_node: Node<'a>,
     program: Program<'a>,
     config: &'a Configuration,
-    external_formatter: Option<&'a ExternalFormatter>,
+    external_formatter: Option<&'a ExternalFormatter<'a>>,
   ) -> Context<'a> {
     Context {
       media_type,
+      specifier,
       program,
       config,
       comments: CommentTracker::new(program, tokens),
@@ -108,9 +178,18 @@ impl<'a> Context<'a> {
       #[cfg(debug_assertions)]
       last_generated_node_pos: deno_ast::SourceTextInfoProvider::text_info(&program).range().start.into(),
       diagnostics: Vec::new(),
+      warnings: Vec::new(),
+      offset_anchors: None,
     }
   }
 
+  /// Gets a condition that records where the position is printed when creating an offset map.
+  pub fn create_offset_anchor(&self, pos: SourcePos, is_end: bool) -> Option<Condition> {
+    let offset_anchors = self.offset_anchors.as_ref()?;
+    let source_start = deno_ast::SourceTextInfoProvider::text_info(&self.program).range().start;
+    Some(offset_anchors.create_condition(pos.as_byte_index(source_start), is_end, self.parent_stack.len()))
+  }
+
   pub fn is_jsx(&self) -> bool {
     matches!(self.media_type, MediaType::Tsx | MediaType::Jsx | MediaType::JavaScript)
   }
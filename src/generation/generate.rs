@@ -11,6 +11,7 @@ use deno_ast::SourcePos;
 use deno_ast::SourceRange;
 use deno_ast::SourceRanged;
 use deno_ast::SourceRangedForSpanned;
+use deno_ast::SourceTextInfoProvider;
 use dprint_core::formatting::condition_resolvers;
 use dprint_core::formatting::conditions::*;
 use dprint_core::formatting::ir_helpers::*;
@@ -23,9 +24,31 @@ use super::swc::get_flattened_bin_expr;
 use super::swc::*;
 use super::*;
 use crate::configuration::*;
+use crate::error::ErrorLocation;
+use crate::error::FormatError;
+use crate::offset_map::OffsetAnchors;
 use crate::utils;
 
+/// Generates the print items, erroring with the first diagnostic (ex. an external formatter failure).
+#[cfg(feature = "tracing")]
 pub fn generate(parsed_source: &ParsedSource, config: &Configuration, external_formatter: Option<&ExternalFormatter>) -> anyhow::Result<PrintItems> {
+  let (items, diagnostics, _) = generate_with_diagnostics(parsed_source, config, external_formatter, None);
+  match diagnostics.into_iter().next() {
+    Some(diagnostic) => Err(diagnostic.into()),
+    None => Ok(items),
+  }
+}
+
+/// Generates the print items along with all the diagnostics and warnings. The embedded
+/// languages the external formatter failed on are left as-is.
+///
+/// Where the nodes and comments are printed is recorded in the offset anchors when provided.
+pub fn generate_with_diagnostics(
+  parsed_source: &ParsedSource,
+  config: &Configuration,
+  external_formatter: Option<&ExternalFormatter>,
+  offset_anchors: Option<OffsetAnchors>,
+) -> (PrintItems, Vec<FormatError>, Vec<FormatError>) {
   // eprintln!("Leading: {:?}", parsed_source.comments().leading_map());
   // eprintln!("Trailing: {:?}", parsed_source.comments().trailing_map());
 
@@ -33,12 +56,14 @@ pub fn generate(parsed_source: &ParsedSource, config: &Configuration, external_f
     let program_node = program.into();
     let mut context = Context::new(
       parsed_source.media_type(),
+      parsed_source.specifier(),
       parsed_source.tokens(),
       program_node,
       program,
       config,
       external_formatter,
     );
+    context.offset_anchors = offset_anchors;
     let mut items = gen_node(program_node, &mut context);
     items.push_condition(if_true(
       "endOfFileNewLine",
@@ -49,18 +74,92 @@ pub fn generate(parsed_source: &ParsedSource, config: &Configuration, external_f
     #[cfg(debug_assertions)]
     context.assert_end_of_file_state();
 
-    if let Some(diagnostic) = context.diagnostics.pop() {
-      return Err(anyhow::anyhow!(diagnostic.message));
-    }
-
-    if config.file_indent_level > 0 {
-      Ok(with_indent_times(items, config.file_indent_level))
+    let items = if config.file_indent_level > 0 {
+      with_indent_times(items, config.file_indent_level)
     } else {
-      Ok(items)
-    }
+      items
+    };
+    (items, context.diagnostics, context.warnings)
+  })
+}
+
+/// Generates only the node the provided function gets from the program (ex. for formatting a snippet).
+///
+/// Returns `None` when the function doesn't provide a node.
+pub fn generate_node(
+  parsed_source: &ParsedSource,
+  config: &Configuration,
+  external_formatter: Option<&ExternalFormatter>,
+  get_node: impl for<'a> FnOnce(Program<'a>) -> Option<Node<'a>>,
+) -> anyhow::Result<Option<PrintItems>> {
+  parsed_source.with_view(|program| {
+    let Some(node) = get_node(program) else {
+      return Ok(None);
+    };
+    generate_in_context(parsed_source, program, node, config, external_formatter, |context| gen_node(node, context))
   })
 }
 
+/// Generates only the statements of the block the provided function gets from the program
+/// without its braces (ex. for formatting the body of a function as a snippet).
+///
+/// Returns `None` when the function doesn't provide a block.
+pub fn generate_block_statements(
+  parsed_source: &ParsedSource,
+  config: &Configuration,
+  external_formatter: Option<&ExternalFormatter>,
+  get_block: impl for<'a> FnOnce(Program<'a>) -> Option<&'a BlockStmt<'a>>,
+) -> anyhow::Result<Option<PrintItems>> {
+  parsed_source.with_view(|program| {
+    let Some(block) = get_block(program) else {
+      return Ok(None);
+    };
+    generate_in_context(parsed_source, program, block.into(), config, external_formatter, |context| {
+      gen_statements(block.get_inner_range(context), block.stmts.iter().map(|x| x.into()).collect(), context)
+    })
+  })
+}
+
+fn generate_in_context<'a>(
+  parsed_source: &'a ParsedSource,
+  program: Program<'a>,
+  node: Node<'a>,
+  config: &'a Configuration,
+  external_formatter: Option<&'a ExternalFormatter<'a>>,
+  gen: impl FnOnce(&mut Context<'a>) -> PrintItems,
+) -> anyhow::Result<Option<PrintItems>> {
+  let mut context = Context::new(
+    parsed_source.media_type(),
+    parsed_source.specifier(),
+    parsed_source.tokens(),
+    node.parent().unwrap_or(node),
+    program,
+    config,
+    external_formatter,
+  );
+  let items = gen(&mut context);
+
+  if let Some(diagnostic) = context.diagnostics.into_iter().next() {
+    return Err(diagnostic.into());
+  }
+
+  Ok(Some(items))
+}
+
+/// Surrounds the items with the offset map's anchors for the start and end positions when creating one.
+fn with_offset_anchors(items: PrintItems, start: SourcePos, end: SourcePos, context: &Context) -> PrintItems {
+  match (context.create_offset_anchor(start, false), context.create_offset_anchor(end, true)) {
+    (Some(start_anchor), Some(end_anchor)) => {
+      let mut new_items = PrintItems::new();
+      new_items.push_condition(start_anchor);
+      new_items.extend(items);
+      new_items.push_condition(end_anchor);
+      new_items
+    }
+    _ => items,
+  }
+}
+
 fn gen_node<'a>(node: Node<'a>, context: &mut Context<'a>) -> PrintItems {
   gen_node_with_inner_gen(node, context, |items, _| items)
 }
@@ -123,7 +222,8 @@ fn gen_node_with_inner_gen<'a>(node: Node<'a>, context: &mut Context<'a>, inner_
     } else {
       node.text_fast(context.program)
     };
-    items.extend(inner_gen(ir_helpers::gen_from_raw_string(node_text), context));
+    let node_items = with_offset_anchors(ir_helpers::gen_from_raw_string(node_text), node_start, node_end, context);
+    items.extend(inner_gen(node_items, context));
 
     // mark any previous comments as handled
     for comment in context.comments.trailing_comments_with_previous(node_end) {
@@ -132,7 +232,15 @@ fn gen_node_with_inner_gen<'a>(node: Node<'a>, context: &mut Context<'a>, inner_
       }
     }
   } else {
-    items.extend(inner_gen(gen_node_inner(node, context), context));
+    let node_items = gen_node_inner(node, context);
+    // don't change the items of nodes that generate nothing and skip template literal text since
+    // the printer's column isn't the text's column at the start of its lines
+    let node_items = if node_items.is_empty() || node_kind == NodeKind::TplElement {
+      node_items
+    } else {
+      with_offset_anchors(node_items, node_start, node_end, context)
+    };
+    items.extend(inner_gen(node_items, context));
   }
 
   // Get the trailing comments -- This needs to be done based on the parse
@@ -3015,67 +3123,127 @@ fn gen_spread_element<'a>(node: &SpreadElement<'a>, context: &mut Context<'a>) -
 /// Formats the tagged template literal using an external formatter.
 /// Detects the type of embedded language automatically.
 fn maybe_gen_tagged_tpl_with_external_formatter<'a>(node: &TaggedTpl<'a>, context: &mut Context<'a>) -> Option<PrintItems> {
+  context.external_formatter.as_ref()?;
+  let language = detect_embedded_language(node, context)?;
+  gen_tpl_with_external_formatter(node.tpl, node.tag.text_fast(context.program), language, node.range(), context)
+}
+
+/// Formats an untagged template literal using an external formatter when its language is known
+/// from a leading comment (ex. `/* sql */ `...``) or from being an Angular component's template or styles.
+fn maybe_gen_untagged_tpl_with_external_formatter<'a>(node: &Tpl<'a>, context: &mut Context<'a>) -> Option<PrintItems> {
+  context.external_formatter.as_ref()?;
+  if matches!(node.parent(), Node::TaggedTpl(_)) {
+    return None;
+  }
+  let language = detect_embedded_language_from_comment(node, context).or_else(|| detect_angular_component_language(node))?;
+  gen_tpl_with_external_formatter(node, "", language, node.range(), context)
+}
+
+/// Formats the template literal with the external formatter, returning `None` when the external formatter
+/// doesn't format the language.
+fn gen_tpl_with_external_formatter<'a>(tpl: &Tpl<'a>, tag_name: &str, language: &str, range: SourceRange, context: &mut Context<'a>) -> Option<PrintItems> {
   let external_formatter = context.external_formatter.as_ref()?;
-  let media_type = detect_embedded_language_type(node)?;
+  let media_type = get_embedded_language_media_type(language);
 
-  let placeholder_css = "@dpr1nt_";
-  let placeholder_other = "dpr1nt_";
   // First creates text with placeholders for the expressions.
-  let placeholder_text = match media_type {
-    MediaType::Css => placeholder_css,
-    _ => placeholder_other,
-  };
+  let placeholders = get_embedded_language_placeholders(tpl, media_type, language);
   let text = capacity_builder::StringBuilder::<String>::build(|builder| {
-    let expr_len = node.tpl.exprs.len();
-    for (i, quasi) in node.tpl.quasis.iter().enumerate() {
-      builder.append(quasi.raw().as_str());
-      if i < expr_len {
-        builder.append(placeholder_text);
-        if i < 10 {
-          // increase chance all placeholders have the same length
-          builder.append("0");
-        }
-        builder.append(i); // give each placeholder a unique name so the formatter doesn't remove duplicates
-        builder.append("_d");
+    for (i, quasi) in tpl.quasis.iter().enumerate() {
+      let quasi_text = quasi.raw().as_str();
+      if i > 0 && placeholders[i - 1].starts_with('#') && !quasi_text.trim_start_matches([' ', '\t']).starts_with(['\n', '\r']) {
+        // end the comment placeholder so it doesn't comment out the rest of the line
+        builder.append('\n');
+      }
+      builder.append(quasi_text);
+      if let Some(placeholder) = placeholders.get(i) {
+        builder.append(placeholder);
       }
     }
   })
   .unwrap();
 
   // Then formats the text with the external formatter.
-  let formatted_tpl = match external_formatter(media_type, text.replace(r"\\", "\\"), context.config) {
+  let text_info = context.program.text_info();
+  let (indent_level, line_start) = get_printed_line_start(tpl, range.start, context);
+  let line_indent_column = indent_level * context.config.indent_width as u32;
+  let start_column = line_indent_column
+    + get_text_width(
+      &collapse_whitespace(&text_info.text_str()[line_start..range.start - text_info.range().start]),
+      context,
+    );
+  let indent_column = line_indent_column + context.config.indent_width as u32;
+  let formatted_tpl = match external_formatter(ExternalFormatRequest {
+    media_type,
+    language,
+    text: text.replace(r"\\", "\\"),
+    config: context.config,
+    tag_name,
+    start_column,
+    indent_column,
+    line_width: context.config.line_width.saturating_sub(indent_column),
+    placeholders: placeholders.clone(),
+  }) {
     Ok(formatted_tpl) => formatted_tpl?.replace("\\", r"\\"),
     Err(err) => {
-      context.diagnostics.push(context::GenerateDiagnostic {
-        message: format!("Error formatting tagged template literal at line {}: {}", node.start_line(), err),
+      let position = text_info.line_and_column_display(range.start);
+      context.diagnostics.push(FormatError::ExternalFormatter {
+        location: ErrorLocation {
+          specifier: context.specifier.clone(),
+          line_number: position.line_number,
+          column_number: position.column_number,
+          range: range.as_byte_range(text_info.range().start),
+        },
+        message: err.to_string(),
       });
       return None;
     }
   };
 
+  // Finds the placeholders in the formatted text and leaves the template as-is
+  // when the external formatter didn't keep all of them in order.
+  let mut lines = Vec::new();
+  let mut index = 0;
+  for line in formatted_tpl.lines() {
+    let mut parts = Vec::new();
+    let mut remaining = line;
+    while let Some(pos) = placeholders.get(index).and_then(|placeholder| remaining.find(placeholder.as_str())) {
+      parts.push((&remaining[..pos], Some(index)));
+      remaining = &remaining[pos + placeholders[index].len()..];
+      index += 1;
+    }
+    parts.push((remaining, None));
+    lines.push(parts);
+  }
+  if index != placeholders.len() {
+    let position = text_info.line_and_column_display(range.start);
+    context.warnings.push(FormatError::ExternalFormatter {
+      location: ErrorLocation {
+        specifier: context.specifier.clone(),
+        line_number: position.line_number,
+        column_number: position.column_number,
+        range: range.as_byte_range(text_info.range().start),
+      },
+      message: format!(
+        "The formatted text did not contain the placeholders for the expressions in order, so the template was left as-is. Missing placeholder: {}",
+        placeholders[index]
+      ),
+    });
+    return None;
+  }
+
   let mut items = PrintItems::new();
   items.push_sc(sc!("`"));
   items.push_signal(Signal::NewLine);
   items.push_signal(Signal::StartIndent);
-  let mut index = 0;
-  for line in formatted_tpl.lines() {
-    let mut pos = 0;
-    let mut parts = line.split(placeholder_text).enumerate().peekable();
-    while let Some((i, part)) = parts.next() {
-      let end = pos + part.len();
-      if i > 0 {
-        pos += part.find("_d").unwrap() + 2;
-      }
-      let text = &line[pos..end];
+  for parts in lines {
+    for (text, expr_index) in parts {
       if !text.is_empty() {
         items.extend(gen_from_raw_string(text));
       }
-      if parts.peek().is_some() {
+      if let Some(expr_index) = expr_index {
         items.push_sc(sc!("${"));
-        items.extend(gen_node(node.tpl.exprs[index].into(), context));
+        items.extend(gen_node(tpl.exprs[expr_index].into(), context));
         items.push_sc(sc!("}"));
-        pos = end + placeholder_text.len();
-        index += 1;
       }
     }
     items.push_signal(Signal::NewLine);
@@ -3085,37 +3253,216 @@ fn maybe_gen_tagged_tpl_with_external_formatter<'a>(node: &TaggedTpl<'a>, contex
   Some(items)
 }
 
-/// Detects the type of embedded language in a tagged template literal.
-fn detect_embedded_language_type<'a>(node: &TaggedTpl<'a>) -> Option<MediaType> {
-  match node.tag {
-    Expr::Ident(ident) => {
-      match ident.sym().as_str() {
-        "css" => Some(MediaType::Css),   // css`...`
-        "html" => Some(MediaType::Html), // html`...`
-        "sql" => Some(MediaType::Sql),   // sql`...`
-        _ => None,
+/// Gets the text to use in place of each expression of the template when formatting it with the external formatter.
+fn get_embedded_language_placeholders(tpl: &Tpl, media_type: MediaType, language: &str) -> Vec<String> {
+  // give each placeholder a unique name so the formatter doesn't remove duplicates
+  // and increase chance all placeholders have the same length
+  let get_placeholder = |prefix: &str, i: usize| format!("{}dpr1nt_{:02}_d", prefix, i);
+  let expr_count = tpl.exprs.len();
+  if media_type == MediaType::Css {
+    return (0..expr_count).map(|i| get_placeholder("@", i)).collect();
+  }
+  if language != "graphql" {
+    return (0..expr_count).map(|i| get_placeholder("", i)).collect();
+  }
+
+  // In GraphQL, a name is valid within a selection set or as a value, but not at the document
+  // level (ex. a fragment definition included in a query), so use a comment there instead.
+  let mut depth = 0usize;
+  let mut in_string = false;
+  let mut in_comment = false;
+  let mut placeholders = Vec::with_capacity(expr_count);
+  let mut is_escaped = false;
+  for (i, quasi) in tpl.quasis.iter().take(expr_count).enumerate() {
+    // this is the text the external formatter receives
+    for c in quasi.raw().as_str().replace(r"\\", "\\").chars() {
+      match c {
+        '\n' | '\r' => in_comment = false,
+        _ if in_comment => {}
+        _ if is_escaped => is_escaped = false,
+        '\\' if in_string => is_escaped = true,
+        '"' => in_string = !in_string,
+        _ if in_string => {}
+        '#' => in_comment = true,
+        '{' | '(' | '[' => depth += 1,
+        '}' | ')' | ']' => depth = depth.saturating_sub(1),
+        _ => {}
       }
     }
-    Expr::Member(member_expr) => {
-      if let Expr::Ident(ident) = member_expr.obj {
-        if ident.sym().as_str() == "styled" {
-          return Some(MediaType::Css); // styled.foo`...`
-        }
+    placeholders.push(if depth == 0 && !in_string && !in_comment {
+      get_placeholder("#", i)
+    } else {
+      get_placeholder("", i)
+    });
+  }
+  placeholders
+}
+
+/// Gets the indent level the line of the template (starting at `start`, which includes the tag)
+/// will be printed at and the byte index of the original text the printed line will start at.
+///
+/// The external formatter is called before anything is printed, so this is based on the file indent
+/// level and the ancestors that indent their contents once formatted rather than the original indentation.
+fn get_printed_line_start(tpl: &Tpl, start: SourcePos, context: &Context) -> (u32, usize) {
+  let program = context.program;
+  let mut indent_level = context.config.file_indent_level;
+  let mut line_start_node: Option<Node> = None;
+  let mut child: Node = tpl.into();
+  for ancestor in tpl.ancestors() {
+    let is_on_later_line = child.start_line_fast(program) > ancestor.start_line_fast(program);
+    let is_indented = match ancestor {
+      Node::BlockStmt(_)
+      | Node::Class(_)
+      | Node::SwitchStmt(_)
+      | Node::SwitchCase(_)
+      | Node::TsModuleBlock(_)
+      | Node::TsInterfaceBody(_)
+      | Node::TsEnumDecl(_)
+      | Node::Module(_)
+      | Node::Script(_) => true,
+      // these keep their items on the first line unless they were on a later line in the original text
+      Node::ObjectLit(_) | Node::ArrayLit(_) | Node::CallExpr(_) | Node::NewExpr(_) | Node::TsTypeLit(_) | Node::JSXElement(_) | Node::JSXFragment(_) => {
+        is_on_later_line
+      }
+      // bodies without braces are indented or get braces
+      Node::IfStmt(stmt) => {
+        !matches!(child, Node::BlockStmt(_) | Node::IfStmt(_))
+          && (child.range() == stmt.cons.range() || stmt.alt.is_some_and(|alt| child.range() == alt.range()))
+      }
+      Node::ForStmt(stmt) => !matches!(child, Node::BlockStmt(_)) && child.range() == stmt.body.range(),
+      Node::ForInStmt(stmt) => !matches!(child, Node::BlockStmt(_)) && child.range() == stmt.body.range(),
+      Node::ForOfStmt(stmt) => !matches!(child, Node::BlockStmt(_)) && child.range() == stmt.body.range(),
+      Node::WhileStmt(stmt) => !matches!(child, Node::BlockStmt(_)) && child.range() == stmt.body.range(),
+      Node::DoWhileStmt(stmt) => !matches!(child, Node::BlockStmt(_)) && child.range() == stmt.body.range(),
+      _ => false,
+    };
+    if is_indented {
+      // the module or script isn't indented, but its items start lines
+      if !matches!(ancestor, Node::Module(_) | Node::Script(_)) {
+        indent_level += 1;
       }
-      None
+      line_start_node.get_or_insert(child);
     }
-    Expr::Call(call_expr) => {
-      if let Callee::Expr(Expr::Ident(ident)) = call_expr.callee {
-        if ident.sym().as_str() == "styled" {
-          return Some(MediaType::Css); // styled(Button)`...`
-        }
-      }
-      None
+    child = ancestor;
+  }
+
+  // the printed line starts at the innermost statement, member or item when it's on the same line
+  let text_info = program.text_info();
+  let original_line_start = text_info.line_start(text_info.line_index(start));
+  let line_start = match line_start_node {
+    Some(node) if node.start() > original_line_start => node.start(),
+    _ => original_line_start,
+  };
+  let line_start = line_start - text_info.range().start;
+  let line_text = &text_info.text_str()[line_start..];
+  (indent_level, line_start + line_text.len() - line_text.trim_start().len())
+}
+
+/// Collapses the whitespace in the text to single spaces the way the surrounding code is printed.
+fn collapse_whitespace(text: &str) -> String {
+  let mut result = String::with_capacity(text.len());
+  let mut was_whitespace = false;
+  for c in text.chars() {
+    if !c.is_whitespace() {
+      result.push(c);
+    } else if !was_whitespace {
+      result.push(' ');
     }
+    was_whitespace = c.is_whitespace();
+  }
+  result
+}
+
+/// Gets the width the text takes up when printed, which is how the printer counts columns.
+fn get_text_width(text: &str, context: &Context) -> u32 {
+  text
+    .chars()
+    .map(|c| match c {
+      '\t' => context.config.indent_width as u32,
+      c => unicode_width::UnicodeWidthChar::width(c).unwrap_or(0) as u32,
+    })
+    .sum()
+}
+
+/// Detects the embedded language of a tagged template literal based on the `taggedTemplates.languages` configuration.
+fn detect_embedded_language<'a>(node: &TaggedTpl, context: &Context<'a>) -> Option<&'a str> {
+  utils::get_language_from_tag_path(&context.config.tagged_templates_languages, get_tag_path(node.tag)?)
+}
+
+/// Detects the embedded language of a template literal from its leading block comment (ex. `/* css */` or `/* GraphQL */`)
+/// based on the `taggedTemplates.languages` configuration.
+fn detect_embedded_language_from_comment<'a>(node: &Tpl, context: &Context<'a>) -> Option<&'a str> {
+  let comment = node.leading_comments_fast(context.program).last()?;
+  if comment.kind != CommentKind::Block {
+    return None;
+  }
+  utils::get_language_from_comment_hint(&context.config.tagged_templates_languages, &comment.text)
+}
+
+/// Detects the template (`template: `...``) and styles (`styles: [`...`]` or `styles: `...``)
+/// in the metadata of an Angular component's `@Component({ ... })` decorator.
+fn detect_angular_component_language(node: &Tpl) -> Option<&'static str> {
+  let mut parent = node.parent();
+  if let Node::ExprOrSpread(expr_or_spread) = parent {
+    // styles: [`...`]
+    let Node::ArrayLit(array_lit) = expr_or_spread.parent() else {
+      return None;
+    };
+    parent = array_lit.parent();
+  }
+  let Node::KeyValueProp(prop) = parent else {
+    return None;
+  };
+  let is_array_item = !matches!(prop.value, Expr::Tpl(_));
+  let language = match prop.key {
+    PropName::Ident(ident) => ident.sym().as_str(),
+    PropName::Str(str) => str.value().as_str(),
+    _ => return None,
+  };
+  let language = match language {
+    "template" if !is_array_item => "html",
+    "styles" => "css",
+    _ => return None,
+  };
+  let Node::ExprOrSpread(arg) = prop.parent().parent() else {
+    return None;
+  };
+  let Node::CallExpr(call_expr) = arg.parent() else {
+    return None;
+  };
+  let is_component_decorator = matches!(call_expr.parent(), Node::Decorator(_))
+    && call_expr.args.first().is_some_and(|first| first.range() == arg.range())
+    && matches!(call_expr.callee, Callee::Expr(Expr::Ident(ident)) if ident.sym() == "Component");
+  is_component_decorator.then_some(language)
+}
+
+/// Gets the path of a tag made of identifiers and member accesses (ex. `styled.div` for `styled.div` and `styled.div.attrs(...)`).
+fn get_tag_path(tag: Expr) -> Option<String> {
+  match tag {
+    Expr::Ident(ident) => Some(ident.sym().to_string()),
+    Expr::Member(member_expr) => match member_expr.prop {
+      MemberProp::Ident(prop) => Some(format!("{}.{}", get_tag_path(member_expr.obj)?, prop.sym())),
+      _ => None,
+    },
+    Expr::Call(call_expr) => match call_expr.callee {
+      Callee::Expr(callee) => get_tag_path(callee),
+      _ => None,
+    },
     _ => None,
   }
 }
 
+/// Gets the media type to provide to the external formatter for an embedded language.
+fn get_embedded_language_media_type(language: &str) -> MediaType {
+  match language {
+    "css" => MediaType::Css,
+    "html" => MediaType::Html,
+    "sql" => MediaType::Sql,
+    "json" => MediaType::Json,
+    _ => MediaType::Unknown,
+  }
+}
+
 fn gen_tagged_tpl<'a>(node: &TaggedTpl<'a>, context: &mut Context<'a>) -> PrintItems {
   let use_space = context.config.tagged_template_space_before_literal;
   let mut items = gen_node(node.tag.into(), context);
@@ -3142,6 +3489,10 @@ fn gen_tagged_tpl<'a>(node: &TaggedTpl<'a>, context: &mut Context<'a>) -> PrintI
 }
 
 fn gen_tpl<'a>(node: &Tpl<'a>, context: &mut Context<'a>) -> PrintItems {
+  if let Some(items) = maybe_gen_untagged_tpl_with_external_formatter(node, context) {
+    return items;
+  }
+
   gen_template_literal(
     node.quasis.iter().map(|&n| n.into()).collect(),
     node.exprs.iter().map(|x| x.into()).collect(),
@@ -6881,7 +7232,7 @@ fn gen_comment(comment: &Comment, context: &mut Context) -> Option<PrintItems> {
   // mark handled and generate
   context.mark_comment_handled(comment);
 
-  return Some(match comment.kind {
+  let items = match comment.kind {
     CommentKind::Block => {
       if has_leading_astrisk_each_line(&comment.text) {
         gen_js_doc_or_multiline_block(comment, context)
@@ -6891,7 +7242,8 @@ fn gen_comment(comment: &Comment, context: &mut Context) -> Option<PrintItems> {
       }
     }
     CommentKind::Line => ir_helpers::gen_js_like_comment_line(&comment.text, context.config.comment_line_force_space_after_slashes),
-  });
+  };
+  return Some(with_offset_anchors(items, comment.start(), comment.end(), context));
 
   fn has_leading_astrisk_each_line(text: &str) -> bool {
     if !text.contains('\n') {
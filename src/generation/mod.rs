@@ -12,5 +12,12 @@ use context::*;
 use generate_types::*;
 use tokens::*;
 
+pub use context::adapt_legacy_external_formatter;
+pub use context::ExternalFormatRequest;
 pub use context::ExternalFormatter;
+pub use context::LegacyExternalFormatter;
+#[cfg(feature = "tracing")]
 pub use generate::generate;
+pub use generate::generate_block_statements;
+pub use generate::generate_node;
+pub use generate::generate_with_diagnostics;
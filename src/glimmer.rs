@@ -0,0 +1,340 @@
+use std::path::Path;
+
+use anyhow::bail;
+use anyhow::Result;
+
+/// Gets the extension to format the script of a Glimmer file (`.gts` or `.gjs`) as
+/// or `None` when the file isn't a Glimmer file.
+pub fn get_glimmer_script_extension(file_path: &Path, file_extension: Option<&str>) -> Option<&'static str> {
+  let extension = file_extension
+    .map(|ext| ext.to_lowercase())
+    .or_else(|| file_path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()));
+  match extension.as_deref() {
+    Some("gts") => Some("ts"),
+    Some("gjs") => Some("js"),
+    _ => None,
+  }
+}
+
+/// Errors for Glimmer files, which are only supported when formatting the whole file
+/// because the templates are extracted from the text.
+pub fn ensure_not_glimmer_file(file_path: &Path, file_extension: Option<&str>, operation: &str) -> Result<()> {
+  if get_glimmer_script_extension(file_path, file_extension).is_some() {
+    bail!("{} is not supported for Glimmer files (.gts and .gjs): {}", operation, file_path.display());
+  }
+  Ok(())
+}
+
+/// The `<template>` blocks of a Glimmer file, which are replaced with identifiers
+/// so the rest of the file can be formatted as JavaScript or TypeScript.
+///
+/// The contents of the templates are kept as-is other than being indented by
+/// as much as the line they start on.
+pub struct GlimmerTemplates {
+  /// The file text with each template replaced by its placeholder.
+  pub script_text: String,
+  templates: Vec<GlimmerTemplate>,
+}
+
+struct GlimmerTemplate {
+  placeholder: String,
+  /// The template text, including a semi-colon that followed it.
+  text: String,
+  /// The indentation of the line the template starts on.
+  indent: String,
+  /// Whether the template is a class member or statement, where the
+  /// formatter may add a semi-colon after the placeholder.
+  is_member_or_statement: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ScanState {
+  Code,
+  LineComment,
+  BlockComment,
+  String(char),
+  Template,
+  RegExp { in_class: bool },
+}
+
+impl GlimmerTemplates {
+  pub fn extract(text: &str) -> Self {
+    let mut script_text = String::with_capacity(text.len());
+    let mut templates = Vec::new();
+    let placeholder_prefix = get_placeholder_prefix(text);
+    let mut state = ScanState::Code;
+    // whether each open brace is the body of a class (a template is then a class member) or
+    // the expression of a template literal (`${`) that returns to the template literal when closed
+    let mut brace_stack: Vec<(bool, bool)> = Vec::new();
+    let mut last_significant_char: Option<char> = None;
+    let mut last_statement_start = 0;
+    let mut pos = 0;
+    while pos < text.len() {
+      let remaining = &text[pos..];
+      let c = remaining.chars().next().unwrap();
+      let mut len = c.len_utf8();
+      match state {
+        ScanState::LineComment => {
+          if c == '\n' {
+            state = ScanState::Code;
+          }
+        }
+        ScanState::BlockComment => {
+          if remaining.starts_with("*/") {
+            state = ScanState::Code;
+            len = 2;
+          }
+        }
+        ScanState::String(quote) => {
+          if c == '\\' {
+            len += remaining[1..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+          } else if c == quote || c == '\n' {
+            state = ScanState::Code;
+          }
+        }
+        ScanState::RegExp { in_class } => {
+          if c == '\\' {
+            len += remaining[1..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+          } else if c == '[' {
+            state = ScanState::RegExp { in_class: true };
+          } else if c == ']' {
+            state = ScanState::RegExp { in_class: false };
+          } else if (c == '/' && !in_class) || c == '\n' {
+            state = ScanState::Code;
+          }
+        }
+        ScanState::Template => {
+          if c == '\\' {
+            len += remaining[1..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+          } else if c == '`' {
+            state = ScanState::Code;
+          } else if remaining.starts_with("${") {
+            brace_stack.push((false, true));
+            state = ScanState::Code;
+            len = 2;
+          }
+        }
+        ScanState::Code => {
+          if remaining.starts_with("//") {
+            state = ScanState::LineComment;
+          } else if remaining.starts_with("/*") {
+            state = ScanState::BlockComment;
+          } else if c == '/' && is_regexp_allowed(&script_text) {
+            state = ScanState::RegExp { in_class: false };
+          } else if c == '\'' || c == '"' {
+            state = ScanState::String(c);
+          } else if c == '`' {
+            state = ScanState::Template;
+          } else if let Some(template_len) = get_template_len(remaining) {
+            let is_class_member = brace_stack.last().is_some_and(|(is_class_body, _)| *is_class_body);
+            let is_member_or_statement = matches!(last_significant_char, None | Some('{') | Some('}') | Some(';'));
+            let mut template_text = remaining[..template_len].to_string();
+            let after_template = &remaining[template_len..];
+            if is_member_or_statement && after_template.trim_start_matches([' ', '\t']).starts_with(';') {
+              let semi_colon_len = after_template.find(';').unwrap() + 1;
+              template_text.push_str(&after_template[..semi_colon_len]);
+            }
+            let placeholder = format!("{}{}_", placeholder_prefix, templates.len());
+            let line_text = &text[text[..pos].rfind('\n').map(|index| index + 1).unwrap_or(0)..pos];
+            script_text.push_str(&placeholder);
+            pos += template_text.len();
+            templates.push(GlimmerTemplate {
+              placeholder,
+              text: template_text,
+              indent: get_indent(line_text).to_string(),
+              is_member_or_statement: is_member_or_statement || is_class_member,
+            });
+            last_significant_char = Some(';');
+            continue;
+          } else if c == '{' {
+            let is_class_body = text[last_statement_start..pos]
+              .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+              .any(|word| word == "class");
+            brace_stack.push((is_class_body, false));
+            last_statement_start = pos + 1;
+          } else if c == '}' {
+            last_statement_start = pos + 1;
+            if let Some((_, true)) = brace_stack.pop() {
+              state = ScanState::Template;
+            }
+          } else if c == ';' {
+            last_statement_start = pos + 1;
+          }
+          if !c.is_whitespace() && state == ScanState::Code {
+            last_significant_char = Some(c);
+          }
+        }
+      }
+      script_text.push_str(&remaining[..len]);
+      pos += len;
+    }
+
+    GlimmerTemplates { script_text, templates }
+  }
+
+  /// Puts the templates back into the formatted script text.
+  pub fn restore(&self, formatted_text: &str) -> Result<String> {
+    let mut text = String::with_capacity(formatted_text.len());
+    let mut remaining = formatted_text;
+    for template in &self.templates {
+      let Some(index) = remaining.find(&template.placeholder) else {
+        bail!("Could not find the placeholder of a <template> in the formatted text. This is a bug in the formatter.");
+      };
+      text.push_str(&remaining[..index]);
+      let line_text = &text[text.rfind('\n').map(|index| index + 1).unwrap_or(0)..];
+      let new_indent = get_indent(line_text).to_string();
+      text.push_str(&reindent(&template.text, &template.indent, &new_indent));
+      remaining = &remaining[index + template.placeholder.len()..];
+      if template.is_member_or_statement {
+        remaining = remaining.strip_prefix(';').unwrap_or(remaining);
+      }
+    }
+    text.push_str(remaining);
+    Ok(text)
+  }
+}
+
+/// Gets a prefix for the placeholders that isn't in the text.
+fn get_placeholder_prefix(text: &str) -> String {
+  let mut prefix = "__dprint_template_".to_string();
+  let mut i = 0;
+  while text.contains(&prefix) {
+    i += 1;
+    prefix = format!("__dprint{}_template_", i);
+  }
+  prefix
+}
+
+/// Gets whether a `/` after the text starts a regular expression rather than being a division.
+fn is_regexp_allowed(text_before: &str) -> bool {
+  fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+  }
+
+  let text = text_before.trim_end();
+  match text.chars().next_back() {
+    None => true,
+    Some(')') | Some(']') => false,
+    Some(c) if is_identifier_char(c) => {
+      let word = &text[text.trim_end_matches(is_identifier_char).len()..];
+      matches!(
+        word,
+        "return" | "typeof" | "instanceof" | "in" | "of" | "new" | "delete" | "void" | "throw" | "case" | "do" | "else" | "yield" | "await"
+      )
+    }
+    Some(_) => true,
+  }
+}
+
+fn get_indent(line_text: &str) -> &str {
+  &line_text[..line_text.len() - line_text.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Changes the indentation of the lines after the first line from the old indent to the new indent.
+fn reindent(text: &str, old_indent: &str, new_indent: &str) -> String {
+  if old_indent == new_indent {
+    return text.to_string();
+  }
+  let mut lines = text.split('\n');
+  let mut result = lines.next().unwrap_or_default().to_string();
+  for line in lines {
+    result.push('\n');
+    match line.strip_prefix(old_indent) {
+      Some(rest) if !line.trim().is_empty() => {
+        result.push_str(new_indent);
+        result.push_str(rest);
+      }
+      _ => result.push_str(line),
+    }
+  }
+  result
+}
+
+/// Gets the length of the `<template>...</template>` at the start of the text, including nested templates.
+fn get_template_len(text: &str) -> Option<usize> {
+  fn is_open_tag(text: &str) -> bool {
+    text
+      .strip_prefix("<template")
+      .is_some_and(|rest| rest.starts_with(|c: char| c == '>' || c.is_whitespace()))
+  }
+
+  if !is_open_tag(text) {
+    return None;
+  }
+  let mut depth = 0;
+  let mut pos = 0;
+  while let Some(index) = text[pos..].find('<') {
+    pos += index;
+    let remaining = &text[pos..];
+    if is_open_tag(remaining) {
+      depth += 1;
+    } else if remaining.starts_with("</template>") {
+      depth -= 1;
+      if depth == 0 {
+        return Some(pos + "</template>".len());
+      }
+    }
+    pos += 1;
+  }
+  None
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn extracts_and_restores_templates() {
+    let text = concat!(
+      "const a = `<template>${`</template>`}`; // <template>\n",
+      "const Greeting = <template><template>Hi</template></template>;\n",
+      "class A extends B {\n  <template>{{this.a}}</template>\n}\n",
+      "<template>\n  <A />\n</template>\n",
+    );
+    let templates = GlimmerTemplates::extract(text);
+    assert_eq!(
+      templates.script_text,
+      concat!(
+        "const a = `<template>${`</template>`}`; // <template>\n",
+        "const Greeting = __dprint_template_0_;\n",
+        "class A extends B {\n  __dprint_template_1_\n}\n",
+        "__dprint_template_2_\n",
+      )
+    );
+    assert_eq!(
+      templates
+        .restore(&templates.script_text.replace("__dprint_template_1_", "__dprint_template_1_;"))
+        .unwrap(),
+      text
+    );
+    assert!(templates.restore("").is_err());
+  }
+
+  #[test]
+  fn uses_placeholders_not_in_text() {
+    let text = "const __dprint_template_0_ = 1;\nconst a = <template>a</template>;\n";
+    let templates = GlimmerTemplates::extract(text);
+    assert_eq!(templates.script_text, "const __dprint_template_0_ = 1;\nconst a = __dprint1_template_0_;\n");
+    assert_eq!(templates.restore(&templates.script_text).unwrap(), text);
+  }
+
+  #[test]
+  fn skips_regexps() {
+    let text = "const a = /<template>[/]/.test(b) / 2;\nconst c = d / 2 / <template>a</template>;\n";
+    let templates = GlimmerTemplates::extract(text);
+    assert_eq!(
+      templates.script_text,
+      "const a = /<template>[/]/.test(b) / 2;\nconst c = d / 2 / __dprint_template_0_;\n"
+    );
+  }
+
+  #[test]
+  fn reindents_templates() {
+    let text = "class A {\n      <template>\n        <p>a</p>\n\n      </template>\n}\n";
+    let templates = GlimmerTemplates::extract(text);
+    assert_eq!(
+      templates.restore(&templates.script_text.replace("      __dprint", "  __dprint")).unwrap(),
+      "class A {\n  <template>\n    <p>a</p>\n\n  </template>\n}\n"
+    );
+  }
+}
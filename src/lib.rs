@@ -10,19 +10,71 @@
 #![deny(clippy::print_stderr)]
 #![deny(clippy::print_stdout)]
 
+mod ast_equivalence;
 pub mod configuration;
+mod error;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod format_component;
+mod format_snippet;
 mod format_text;
+mod formatter;
 mod generation;
+mod glimmer;
+#[cfg(feature = "tracing")]
+mod line_break_explanation;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod offset_map;
 mod swc;
+mod text_edit;
 mod utils;
 
+pub use error::ErrorLocation;
+pub use error::FormatError;
+pub use error::FormatErrorKind;
+pub use error::SyntaxDiagnostic;
+pub use format_component::format_component_text;
+pub use format_snippet::format_expression;
+pub use format_snippet::format_statements;
+pub use format_snippet::format_type;
+pub use format_snippet::FormatSnippetOptions;
+pub use format_text::adapt_legacy_external_formatter;
 pub use format_text::format_parsed_source;
 pub use format_text::format_text;
+pub use format_text::format_text_edits;
+pub use format_text::format_text_lenient;
+pub use format_text::format_text_range;
+pub use format_text::format_text_with_offset_map;
+pub use format_text::format_text_with_warnings;
+pub use format_text::ExternalFormatRequest;
 pub use format_text::ExternalFormatter;
+pub use format_text::FormatTextLenientResult;
 pub use format_text::FormatTextOptions;
+pub use format_text::FormatTextRangeOptions;
+pub use format_text::FormatTextRangeResult;
+pub use format_text::FormatTextWithOffsetMapResult;
+pub use format_text::FormatTextWithWarningsResult;
+pub use format_text::LegacyExternalFormatter;
+pub use formatter::Formatter;
+#[cfg(feature = "lsp")]
+pub use lsp::run_lsp_server;
+pub use offset_map::OffsetMap;
+pub use text_edit::TextEdit;
 
 #[cfg(feature = "tracing")]
 pub use format_text::trace_file;
+#[cfg(feature = "tracing")]
+pub use line_break_explanation::*;
+
+#[cfg(any(feature = "process", all(feature = "wasm", target_arch = "wasm32", target_os = "unknown")))]
+mod plugin_handler;
+
+#[cfg(any(feature = "process", all(feature = "wasm", target_arch = "wasm32", target_os = "unknown")))]
+pub use plugin_handler::TypeScriptPluginHandler;
+
+#[cfg(feature = "process")]
+mod process_plugin;
 
 #[cfg(feature = "wasm")]
 #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
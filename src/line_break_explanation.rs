@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Result;
+use deno_ast::view::*;
+use deno_ast::SourceRanged;
+use dprint_core::formatting::*;
+
+use super::configuration::Configuration;
+use super::error::FormatError;
+use super::format_text::ConfigPrintOptions;
+use super::generation::generate;
+use super::swc::ensure_no_specific_syntax_errors;
+use super::swc::parse_swc_ast;
+use super::swc::reparse_swc_ast;
+
+/// Explains why a line of the formatted text starts on a new line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineBreakExplanation {
+  /// 1-indexed line number in the formatted text.
+  pub line_number: usize,
+  pub reason: LineBreakReason,
+  /// The kind of the innermost node the line break is in (ex. `CallExpr`).
+  pub node_kind: String,
+  /// The conditions the line break is within from outermost to innermost along with what they resolved to.
+  pub conditions: Vec<(String, bool)>,
+  /// The configuration keys that may change whether the line breaks.
+  pub config_keys: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineBreakReason {
+  /// The line would have exceeded the line width.
+  ExceededLineWidth { line_width: u32 },
+  /// A condition decided the code should be on a new line (ex. because
+  /// the node is multi-line or doesn't fit within the line width).
+  Condition { name: String },
+  /// The code is always on a new line here (ex. statements).
+  Required,
+  /// The previous line ends with a line comment.
+  AfterLineComment,
+  /// A blank line in the original text is kept.
+  BlankLine,
+}
+
+impl fmt::Display for LineBreakExplanation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Line {} starts on a new line ", self.line_number)?;
+    match &self.reason {
+      LineBreakReason::ExceededLineWidth { line_width } => write!(f, "because the line would have exceeded the line width of {}", line_width)?,
+      LineBreakReason::Condition { name } => write!(f, "because the `{}` condition resolved to true", name)?,
+      LineBreakReason::Required => write!(f, "because the formatter requires it here")?,
+      LineBreakReason::AfterLineComment => write!(f, "because the previous line ends with a line comment")?,
+      LineBreakReason::BlankLine => write!(f, "because blank lines from the original text are kept")?,
+    }
+    write!(f, " (node: {}).", self.node_kind)?;
+    if !self.conditions.is_empty() {
+      write!(f, "\nDecided by: ")?;
+      for (i, (name, value)) in self.conditions.iter().enumerate() {
+        if i > 0 {
+          write!(f, " > ")?;
+        }
+        write!(f, "{} ({})", name, value)?;
+      }
+    }
+    if !self.config_keys.is_empty() {
+      write!(f, "\nConfiguration that may change this: {}", self.config_keys.join(", "))?;
+    }
+    Ok(())
+  }
+}
+
+/// Explains why the provided line of the formatted text starts on a new line.
+///
+/// Returns `None` when the formatted text doesn't have the line or for the first line.
+pub fn explain_line_break(file_path: &Path, file_text: &str, config: &Configuration, line_number: usize) -> Result<Option<LineBreakExplanation>> {
+  let parsed_source = parse_swc_ast(file_path, None, file_text.into())?;
+  ensure_no_specific_syntax_errors(&parsed_source)?;
+  let mut maybe_err: Box<Option<anyhow::Error>> = Box::new(None);
+  let result = dprint_core::formatting::trace_printing(
+    || match generate(&parsed_source, config, None) {
+      Ok(print_items) => print_items,
+      Err(e) => {
+        maybe_err.replace(e);
+        PrintItems::default()
+      }
+    },
+    ConfigPrintOptions::new(config).for_file(file_text),
+  );
+  if let Some(e) = maybe_err.take() {
+    return Err(e);
+  }
+  if line_number < 2 {
+    return Ok(None);
+  }
+
+  // find the new line that starts the line in the final writer nodes
+  let writer_nodes = result.writer_nodes.iter().map(|node| (node.writer_node_id, node)).collect::<HashMap<_, _>>();
+  let mut final_nodes = Vec::new();
+  let mut current = result.traces.last().and_then(|trace| trace.writer_node_id);
+  while let Some(node) = current.and_then(|id| writer_nodes.get(&id)) {
+    final_nodes.push(*node);
+    current = node.previous_node_id;
+  }
+  final_nodes.reverse();
+  let mut formatted_offset = 0;
+  let mut new_line_count = 0;
+  let mut found_index = None;
+  for (i, node) in final_nodes.iter().enumerate() {
+    if node.text.ends_with('\n') {
+      new_line_count += 1;
+      if new_line_count == line_number - 1 {
+        found_index = Some(i);
+        break;
+      }
+    }
+    formatted_offset += node.text.len();
+  }
+  let Some(found_index) = found_index else {
+    return Ok(None);
+  };
+  let new_line_node = final_nodes[found_index];
+  let formatted_text = final_nodes.iter().map(|node| node.text.as_str()).collect::<String>();
+
+  // find the print node that wrote the new line
+  let print_nodes = result.print_nodes.iter().map(|node| (node.print_node_id, node)).collect::<HashMap<_, _>>();
+  let writing_trace = result
+    .traces
+    .iter()
+    .position(|trace| trace.writer_node_id == Some(new_line_node.writer_node_id))
+    .and_then(|trace_index| {
+      let print_node = print_nodes.get(&result.traces[trace_index].print_node_id)?;
+      Some((trace_index, print_node))
+    });
+  let Some((trace_index, writing_print_node)) = writing_trace else {
+    return Err(
+      FormatError::Internal {
+        location: None,
+        message: format!(
+          "Could not find the print item that wrote the line break before line {}. This is a bug in the formatter.",
+          line_number
+        ),
+      }
+      .into(),
+    );
+  };
+  let writing_print_node_id = writing_print_node.print_node_id;
+  let is_blank_line = found_index > 0 && final_nodes[found_index - 1].text.ends_with('\n');
+  let (reason, location_print_node_id) = match &writing_print_node.print_item {
+    _ if is_blank_line => (LineBreakReason::BlankLine, writing_print_node_id),
+    TracePrintItem::Signal(Signal::SpaceOrNewLine) => (LineBreakReason::ExceededLineWidth { line_width: config.line_width }, writing_print_node_id),
+    TracePrintItem::String(_) => {
+      // when the text exceeds the line width, the printer goes back to the last possible
+      // new line, writes a new line, then continues from the print node after it
+      let next_print_node_id = result.traces.get(trace_index + 1).map(|trace| trace.print_node_id);
+      match next_print_node_id {
+        Some(next_id) if result.traces[..trace_index].iter().any(|trace| trace.print_node_id == next_id) => {
+          (LineBreakReason::ExceededLineWidth { line_width: config.line_width }, next_id)
+        }
+        _ => (LineBreakReason::AfterLineComment, writing_print_node_id),
+      }
+    }
+    _ => (LineBreakReason::Required, writing_print_node_id),
+  };
+
+  let conditions = get_conditions(&result.print_nodes, &print_nodes, location_print_node_id);
+  let reason = match reason {
+    LineBreakReason::Required => match conditions.iter().rev().find(|(_, value)| *value) {
+      Some((name, _)) => LineBreakReason::Condition { name: name.clone() },
+      None => reason,
+    },
+    reason => reason,
+  };
+  let formatted_source = reparse_swc_ast(&parsed_source, formatted_text.into())?;
+  let node_kinds = formatted_source.with_view(|program| get_node_kinds_at(program.into(), formatted_offset, &formatted_source));
+  let mut config_keys = Vec::new();
+  if matches!(reason, LineBreakReason::ExceededLineWidth { .. }) {
+    config_keys.push("lineWidth");
+  }
+  if reason != LineBreakReason::BlankLine {
+    if let Some(keys) = node_kinds.iter().find_map(|kind| get_config_keys(*kind)) {
+      config_keys.extend(keys);
+      if matches!(reason, LineBreakReason::Condition { .. }) {
+        // these conditions are commonly resolved based on whether the node fits within the line width
+        config_keys.push("lineWidth");
+      }
+    }
+  }
+
+  Ok(Some(LineBreakExplanation {
+    line_number,
+    reason,
+    node_kind: format!("{:?}", node_kinds[0]),
+    conditions,
+    config_keys,
+  }))
+}
+
+/// Gets the conditions the print node is within from outermost to innermost.
+fn get_conditions(all_print_nodes: &[TracePrintNode], print_nodes: &HashMap<u32, &TracePrintNode>, print_node_id: u32) -> Vec<(String, bool)> {
+  // map each print node to the condition path or rc path it's in
+  let mut parents: HashMap<u32, (u32, Option<bool>)> = HashMap::new();
+  let mut add_path = |path_id: Option<u32>, parent: (u32, Option<bool>)| {
+    let mut current = path_id;
+    while let Some(id) = current {
+      if parents.contains_key(&id) {
+        break;
+      }
+      parents.insert(id, parent);
+      current = print_nodes.get(&id).and_then(|node| node.next_print_node_id);
+    }
+  };
+  for node in all_print_nodes {
+    match &node.print_item {
+      TracePrintItem::Condition(condition) => {
+        add_path(condition.true_path, (node.print_node_id, Some(true)));
+        add_path(condition.false_path, (node.print_node_id, Some(false)));
+      }
+      TracePrintItem::RcPath(path_id) => add_path(Some(*path_id), (node.print_node_id, None)),
+      _ => {}
+    }
+  }
+
+  let mut conditions = Vec::new();
+  let mut current = parents.get(&print_node_id);
+  while let Some((parent_id, value)) = current {
+    if let (Some(value), TracePrintItem::Condition(condition)) = (value, &print_nodes[parent_id].print_item) {
+      conditions.push((condition.name.clone(), *value));
+    }
+    current = parents.get(parent_id);
+  }
+  conditions.reverse();
+  conditions
+}
+
+/// Gets the kinds of the nodes that contain the byte offset from innermost to outermost.
+fn get_node_kinds_at(node: Node, offset: usize, parsed_source: &deno_ast::ParsedSource) -> Vec<NodeKind> {
+  let source_start = parsed_source.range().start;
+  let mut kinds = vec![node.kind()];
+  let mut current = node;
+  while let Some(child) = current.children().into_iter().find(|child| {
+    let range = child.range().as_byte_range(source_start);
+    range.start < offset && offset < range.end
+  }) {
+    kinds.push(child.kind());
+    current = child;
+  }
+  kinds.reverse();
+  kinds
+}
+
+fn get_config_keys(kind: NodeKind) -> Option<Vec<&'static str>> {
+  Some(match kind {
+    NodeKind::CallExpr | NodeKind::OptCall | NodeKind::NewExpr => vec!["arguments.preferHanging", "arguments.preferSingleLine"],
+    NodeKind::Function | NodeKind::ArrowExpr | NodeKind::Constructor | NodeKind::TsFnType | NodeKind::TsMethodSignature => {
+      vec!["parameters.preferHanging", "parameters.preferSingleLine"]
+    }
+    NodeKind::ArrayLit => vec!["arrayExpression.preferHanging", "arrayExpression.preferSingleLine"],
+    NodeKind::ArrayPat => vec!["arrayPattern.preferHanging", "arrayPattern.preferSingleLine"],
+    NodeKind::ObjectLit => vec!["objectExpression.preferHanging", "objectExpression.preferSingleLine"],
+    NodeKind::ObjectPat => vec!["objectPattern.preferHanging", "objectPattern.preferSingleLine"],
+    NodeKind::BinExpr => vec![
+      "binaryExpression.operatorPosition",
+      "binaryExpression.linePerExpression",
+      "binaryExpression.preferSingleLine",
+    ],
+    NodeKind::CondExpr => vec![
+      "conditionalExpression.operatorPosition",
+      "conditionalExpression.linePerExpression",
+      "conditionalExpression.preferSingleLine",
+    ],
+    NodeKind::MemberExpr => vec!["memberExpression.linePerExpression", "memberExpression.preferSingleLine"],
+    NodeKind::SeqExpr => vec!["sequenceExpression.preferHanging"],
+    NodeKind::ParenExpr => vec!["parentheses.preferSingleLine"],
+    NodeKind::TsUnionType | NodeKind::TsIntersectionType => vec!["unionAndIntersectionType.preferHanging", "unionAndIntersectionType.preferSingleLine"],
+    NodeKind::TsTypeLit => vec!["typeLiteral.preferHanging", "typeLiteral.preferSingleLine"],
+    NodeKind::TsTupleType => vec!["tupleType.preferHanging", "tupleType.preferSingleLine"],
+    NodeKind::TsMappedType => vec!["mappedType.preferSingleLine"],
+    NodeKind::TsConditionalType => vec!["conditionalType.operatorPosition", "conditionalType.preferSingleLine"],
+    NodeKind::TsTypeParamDecl | NodeKind::TsTypeParamInstantiation => vec!["typeParameters.preferHanging", "typeParameters.preferSingleLine"],
+    NodeKind::ImportDecl => vec!["importDeclaration.preferHanging", "importDeclaration.preferSingleLine"],
+    NodeKind::NamedExport => vec!["exportDeclaration.preferHanging", "exportDeclaration.preferSingleLine"],
+    NodeKind::VarDecl => vec!["variableStatement.preferHanging", "variableStatement.preferSingleLine"],
+    NodeKind::Decorator => vec!["decorators.preferSingleLine"],
+    NodeKind::JSXOpeningElement => vec!["jsxAttributes.preferHanging", "jsxAttributes.preferSingleLine"],
+    NodeKind::JSXElement | NodeKind::JSXFragment => vec!["jsxElement.preferSingleLine", "jsx.multiLineParens"],
+    NodeKind::IfStmt => vec![
+      "ifStatement.useBraces",
+      "ifStatement.singleBodyPosition",
+      "ifStatement.nextControlFlowPosition",
+      "ifStatement.preferHanging",
+    ],
+    NodeKind::ForStmt => vec![
+      "forStatement.useBraces",
+      "forStatement.singleBodyPosition",
+      "forStatement.preferHanging",
+      "forStatement.preferSingleLine",
+    ],
+    NodeKind::ForInStmt => vec!["forInStatement.useBraces", "forInStatement.singleBodyPosition", "forInStatement.preferHanging"],
+    NodeKind::ForOfStmt => vec!["forOfStatement.useBraces", "forOfStatement.singleBodyPosition", "forOfStatement.preferHanging"],
+    NodeKind::WhileStmt => vec!["whileStatement.useBraces", "whileStatement.singleBodyPosition", "whileStatement.preferHanging"],
+    NodeKind::DoWhileStmt => vec!["doWhileStatement.nextControlFlowPosition", "doWhileStatement.preferHanging"],
+    NodeKind::TryStmt => vec!["tryStatement.nextControlFlowPosition"],
+    NodeKind::SwitchStmt => vec!["switchStatement.preferHanging"],
+    _ => return None,
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::configuration::ConfigurationBuilder;
+
+  fn explain(text: &str, line_number: usize) -> Option<LineBreakExplanation> {
+    let config = ConfigurationBuilder::new().line_width(40).build();
+    explain_line_break(&std::path::PathBuf::from("test.ts"), text, &config, line_number).unwrap()
+  }
+
+  #[test]
+  fn explains_line_breaks() {
+    let text = "call(aaaaaaaaaaaaaaa, bbbbbbbbbbbbbbb, ccccccccccc);\n\nlet a = 1;\nlet b = 2;\nconst value = \"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\";\n";
+    assert_eq!(explain(text, 1), None);
+    assert_eq!(explain(text, 100), None);
+
+    let explanation = explain(text, 2).unwrap();
+    assert_eq!(
+      explanation.reason,
+      LineBreakReason::Condition {
+        name: "newLineIndentedIfNotStandalone".to_string()
+      }
+    );
+    assert_eq!(explanation.node_kind, "CallExpr");
+    assert_eq!(
+      explanation.config_keys,
+      vec!["arguments.preferHanging", "arguments.preferSingleLine", "lineWidth"]
+    );
+    assert_eq!(
+      explanation.to_string(),
+      concat!(
+        "Line 2 starts on a new line because the `newLineIndentedIfNotStandalone` condition resolved to true (node: CallExpr).\n",
+        "Decided by: withIndentIfStartOfLineIndented (false) > multiLineOrHanging (true) > newLineIndentedIfNotStandalone (true)\n",
+        "Configuration that may change this: arguments.preferHanging, arguments.preferSingleLine, lineWidth",
+      )
+    );
+
+    let explanation = explain(text, 7).unwrap();
+    assert_eq!(explanation.reason, LineBreakReason::BlankLine);
+    assert!(explanation.config_keys.is_empty());
+
+    let explanation = explain(text, 8).unwrap();
+    assert_eq!(explanation.reason, LineBreakReason::Required);
+    assert_eq!(explanation.node_kind, "Script");
+
+    let explanation = explain(text, 10).unwrap();
+    assert_eq!(explanation.reason, LineBreakReason::ExceededLineWidth { line_width: 40 });
+    assert_eq!(explanation.node_kind, "VarDeclarator");
+    assert_eq!(
+      explanation.config_keys,
+      vec!["lineWidth", "variableStatement.preferHanging", "variableStatement.preferSingleLine"]
+    );
+
+    let explanation = explain(text, 11).unwrap();
+    assert_eq!(
+      explanation.reason,
+      LineBreakReason::Condition {
+        name: "endOfFileNewLine".to_string()
+      }
+    );
+    assert!(explanation.config_keys.is_empty());
+  }
+}
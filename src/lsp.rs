@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::GlobalConfiguration;
+use serde_json::json;
+use serde_json::Value;
+
+use super::configuration::resolve_config;
+use super::configuration::resolve_config_file_map;
+use super::configuration::resolve_config_file_text;
+use super::configuration::Configuration;
+use super::error::FormatError;
+use super::format_text::format_text_edits;
+use super::format_text::format_text_range;
+use super::format_text::FormatTextOptions;
+use super::format_text::FormatTextRangeOptions;
+use super::text_edit::TextEdit;
+
+const INVALID_REQUEST: i64 = -32600;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+const REQUEST_FAILED: i64 = -32803;
+
+/// The configuration files searched for in the workspace root when the client
+/// doesn't provide the configuration in the initialization options.
+const CONFIG_FILE_NAMES: [&str; 4] = ["dprint.json", "dprint.jsonc", ".dprint.json", ".dprint.jsonc"];
+
+/// Runs a language server that provides document, range and on type formatting
+/// over the provided reader and writer (ex. stdin and stdout).
+///
+/// The configuration is resolved from the `initializationOptions` of the `initialize`
+/// request, which is either a dprint configuration file's object or the plugin configuration,
+/// or otherwise from a `dprint.json` or `dprint.jsonc` file in the workspace root. When there is no configuration,
+/// the editor's tab size and whether it inserts spaces are used.
+///
+/// Returns when the client sends the `exit` notification or closes the reader.
+pub fn run_lsp_server(mut reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+  let mut server = Server::default();
+  while let Some(message) = read_message(&mut reader)? {
+    let Some(method) = message.get("method").and_then(|method| method.as_str()) else {
+      continue; // a response to a request from the server, which it doesn't send
+    };
+    if method == "exit" {
+      return Ok(());
+    }
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    match message.get("id") {
+      Some(id) => {
+        let response = match server.handle_request(method, params) {
+          Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+          Err(err) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": err.code, "message": err.message } }),
+        };
+        write_message(&mut writer, &response)?;
+      }
+      None => server.handle_notification(method, params),
+    }
+  }
+  Ok(())
+}
+
+struct ResponseError {
+  code: i64,
+  message: String,
+}
+
+impl ResponseError {
+  fn new(code: i64, message: impl Into<String>) -> Self {
+    Self { code, message: message.into() }
+  }
+}
+
+impl From<anyhow::Error> for ResponseError {
+  fn from(err: anyhow::Error) -> Self {
+    ResponseError::new(REQUEST_FAILED, format!("{:#}", err))
+  }
+}
+
+#[derive(Default)]
+struct Server {
+  is_initialized: bool,
+  is_shut_down: bool,
+  /// The resolved configuration or `None` to use the editor's formatting options.
+  config: Option<Configuration>,
+  documents: HashMap<String, String>,
+}
+
+impl Server {
+  fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, ResponseError> {
+    if self.is_shut_down {
+      return Err(ResponseError::new(INVALID_REQUEST, "The server was shut down."));
+    }
+    if method == "initialize" {
+      if self.is_initialized {
+        return Err(ResponseError::new(INVALID_REQUEST, "The server was already initialized."));
+      }
+      self.config = resolve_initialize_config(&params).map_err(|err| ResponseError::new(INVALID_PARAMS, format!("{:#}", err)))?;
+      self.is_initialized = true;
+      return Ok(json!({
+        "capabilities": {
+          "textDocumentSync": 1,
+          "documentFormattingProvider": true,
+          "documentRangeFormattingProvider": true,
+          "documentOnTypeFormattingProvider": {
+            "firstTriggerCharacter": ";",
+            "moreTriggerCharacter": ["}", "\n"],
+          },
+        },
+        "serverInfo": {
+          "name": env!("CARGO_PKG_NAME"),
+          "version": env!("CARGO_PKG_VERSION"),
+        },
+      }));
+    }
+    if !self.is_initialized {
+      return Err(ResponseError::new(SERVER_NOT_INITIALIZED, "The server was not initialized."));
+    }
+
+    match method {
+      "shutdown" => {
+        self.is_shut_down = true;
+        Ok(Value::Null)
+      }
+      "textDocument/formatting" => {
+        let (path, text) = self.get_document(&params)?;
+        let config = self.get_config(&params);
+        let edits = format_document(&path, text, &config)?;
+        Ok(to_lsp_edits(text, edits))
+      }
+      "textDocument/rangeFormatting" => {
+        let (path, text) = self.get_document(&params)?;
+        let config = self.get_config(&params);
+        let line_index = LineIndex::new(text);
+        let range = line_index.offset(&params["range"]["start"])..line_index.offset(&params["range"]["end"]);
+        let edits = format_range(&path, text, range, &config)?;
+        Ok(to_lsp_edits(text, edits))
+      }
+      "textDocument/onTypeFormatting" => {
+        let (path, text) = self.get_document(&params)?;
+        let config = self.get_config(&params);
+        let line_index = LineIndex::new(text);
+        let offset = line_index.offset(&params["position"]);
+        let line_start = text[..offset].rfind('\n').map(|index| index + 1).unwrap_or(0);
+        let range = if params["ch"].as_str() == Some("\n") {
+          // format the line that was ended
+          let previous_line_end = line_start.saturating_sub(1);
+          let previous_line_start = text[..previous_line_end].rfind('\n').map(|index| index + 1).unwrap_or(0);
+          previous_line_start..previous_line_end
+        } else {
+          line_start..offset
+        };
+        match format_range(&path, text, range, &config) {
+          Ok(edits) => Ok(to_lsp_edits(text, edits)),
+          // the code is often incomplete while typing
+          Err(err) if matches!(err.downcast_ref::<FormatError>(), Some(FormatError::Syntax(_))) => Ok(Value::Null),
+          Err(err) => Err(err.into()),
+        }
+      }
+      _ => Err(ResponseError::new(METHOD_NOT_FOUND, format!("Unhandled method: {}", method))),
+    }
+  }
+
+  fn handle_notification(&mut self, method: &str, params: Value) {
+    let Some(uri) = params["textDocument"]["uri"].as_str() else {
+      return;
+    };
+    match method {
+      "textDocument/didOpen" => {
+        if let Some(text) = params["textDocument"]["text"].as_str() {
+          self.documents.insert(uri.to_string(), text.to_string());
+        }
+      }
+      "textDocument/didChange" => {
+        // the server only supports full document synchronization
+        let changes = params["contentChanges"].as_array();
+        if let Some(text) = changes.and_then(|changes| changes.last()).and_then(|change| change["text"].as_str()) {
+          self.documents.insert(uri.to_string(), text.to_string());
+        }
+      }
+      "textDocument/didClose" => {
+        self.documents.remove(uri);
+      }
+      _ => {}
+    }
+  }
+
+  fn get_document(&self, params: &Value) -> Result<(PathBuf, &str), ResponseError> {
+    let Some(uri) = params["textDocument"]["uri"].as_str() else {
+      return Err(ResponseError::new(INVALID_PARAMS, "Expected a text document."));
+    };
+    let Some(text) = self.documents.get(uri) else {
+      return Err(ResponseError::new(INVALID_PARAMS, format!("The document was not opened: {}", uri)));
+    };
+    Ok((uri_to_path(uri), text))
+  }
+
+  fn get_config(&self, params: &Value) -> Configuration {
+    match &self.config {
+      Some(config) => config.clone(),
+      None => {
+        let options = &params["options"];
+        let global_config = GlobalConfiguration {
+          indent_width: options["tabSize"].as_u64().map(|tab_size| tab_size as u8),
+          use_tabs: options["insertSpaces"].as_bool().map(|insert_spaces| !insert_spaces),
+          ..Default::default()
+        };
+        resolve_config(ConfigKeyMap::new(), &global_config).config
+      }
+    }
+  }
+}
+
+fn resolve_initialize_config(params: &Value) -> Result<Option<Configuration>> {
+  match &params["initializationOptions"] {
+    Value::Object(options) if !options.is_empty() => {
+      let config_map: ConfigKeyMap = serde_json::from_value(Value::Object(options.clone()))?;
+      return resolve_config_file_map(config_map).map(Some);
+    }
+    _ => {}
+  }
+
+  let root_path = match params["rootUri"].as_str() {
+    Some(root_uri) => Some(uri_to_path(root_uri)),
+    None => params["rootPath"].as_str().map(PathBuf::from),
+  };
+  let Some(root_path) = root_path else {
+    return Ok(None);
+  };
+  for file_name in CONFIG_FILE_NAMES {
+    let config_path = root_path.join(file_name);
+    if config_path.is_file() {
+      let text = std::fs::read_to_string(&config_path).with_context(|| format!("Failed reading {}", config_path.display()))?;
+      return resolve_config_file_text(&text)
+        .map(Some)
+        .with_context(|| format!("Failed resolving {}", config_path.display()));
+    }
+  }
+  Ok(None)
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+  match ModuleSpecifier::parse(uri) {
+    Ok(specifier) => specifier.to_file_path().unwrap_or_else(|_| PathBuf::from(specifier.path())),
+    Err(_) => PathBuf::from(uri),
+  }
+}
+
+fn format_document(path: &Path, text: &str, config: &Configuration) -> Result<Vec<TextEdit>> {
+  format_text_edits(FormatTextOptions {
+    path,
+    extension: None,
+    text: text.to_string(),
+    config,
+    external_formatter: None,
+  })
+}
+
+fn format_range(path: &Path, text: &str, range: Range<usize>, config: &Configuration) -> Result<Vec<TextEdit>> {
+  let result = format_text_range(FormatTextRangeOptions {
+    path,
+    extension: None,
+    text: text.to_string(),
+    range,
+    config,
+    external_formatter: None,
+  })?;
+  Ok(
+    result
+      .map(|result| {
+        // the text before and after the formatted range didn't change
+        let original_end = text.len() - (result.text.len() - result.range.end);
+        TextEdit {
+          range: result.range.start..original_end,
+          new_text: result.text[result.range].to_string(),
+        }
+      })
+      .into_iter()
+      .collect(),
+  )
+}
+
+fn to_lsp_edits(text: &str, edits: Vec<TextEdit>) -> Value {
+  let line_index = LineIndex::new(text);
+  edits
+    .into_iter()
+    .map(|edit| {
+      json!({
+        "range": {
+          "start": line_index.position(edit.range.start),
+          "end": line_index.position(edit.range.end),
+        },
+        "newText": edit.new_text,
+      })
+    })
+    .collect()
+}
+
+/// Converts between byte offsets and LSP positions, whose characters are UTF-16 code units.
+struct LineIndex<'a> {
+  text: &'a str,
+  line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+  fn new(text: &'a str) -> Self {
+    let mut line_starts = vec![0];
+    line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+    Self { text, line_starts }
+  }
+
+  fn offset(&self, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let Some(&line_start) = self.line_starts.get(line) else {
+      return self.text.len();
+    };
+    let mut utf16_count = 0;
+    for (index, c) in self.text[line_start..].char_indices() {
+      if utf16_count >= character || c == '\n' {
+        return line_start + index;
+      }
+      utf16_count += c.len_utf16();
+    }
+    self.text.len()
+  }
+
+  fn position(&self, offset: usize) -> Value {
+    let line = self.line_starts.partition_point(|&line_start| line_start <= offset) - 1;
+    let character = self.text[self.line_starts[line]..offset].encode_utf16().count();
+    json!({ "line": line, "character": character })
+  }
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+  let mut content_length = None;
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      if name.eq_ignore_ascii_case("Content-Length") {
+        content_length = Some(value.trim().parse::<usize>()?);
+      }
+    }
+  }
+  let Some(content_length) = content_length else {
+    bail!("Expected a Content-Length header.");
+  };
+  let mut content = vec![0; content_length];
+  reader.read_exact(&mut content)?;
+  Ok(Some(serde_json::from_slice(&content)?))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+  let content = serde_json::to_string(message)?;
+  write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+  writer.flush()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn run_session(messages: Vec<Value>) -> Vec<Value> {
+    let mut input = Vec::new();
+    for message in messages {
+      write_message(&mut input, &message).unwrap();
+    }
+    let mut output = Vec::new();
+    run_lsp_server(input.as_slice(), &mut output).unwrap();
+    let mut reader = output.as_slice();
+    let mut responses = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+      responses.push(message);
+    }
+    responses
+  }
+
+  fn request(id: u32, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+  }
+
+  fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+  }
+
+  fn position(line: u32, character: u32) -> Value {
+    json!({ "line": line, "character": character })
+  }
+
+  fn edit(start: Value, end: Value, new_text: &str) -> Value {
+    json!({ "range": { "start": start, "end": end }, "newText": new_text })
+  }
+
+  #[test]
+  fn formats_documents() {
+    let text_document = json!({ "uri": "file:///project/file.ts" });
+    let responses = run_session(vec![
+      request(1, "textDocument/formatting", json!({ "textDocument": text_document })),
+      request(
+        2,
+        "initialize",
+        json!({ "initializationOptions": { "lineWidth": 40, "typescript": { "semiColons": "asi" } } }),
+      ),
+      notification("initialized", json!({})),
+      notification(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": "file:///project/file.ts", "languageId": "typescript", "version": 1, "text": "const  a = '🚀';\nlet b=1;" } }),
+      ),
+      request(
+        3,
+        "textDocument/formatting",
+        json!({ "textDocument": text_document, "options": { "tabSize": 4, "insertSpaces": true } }),
+      ),
+      request(
+        4,
+        "textDocument/rangeFormatting",
+        json!({ "textDocument": text_document, "range": { "start": position(1, 0), "end": position(1, 8) } }),
+      ),
+      request(
+        5,
+        "textDocument/onTypeFormatting",
+        json!({ "textDocument": text_document, "position": position(1, 8), "ch": ";" }),
+      ),
+      notification(
+        "textDocument/didChange",
+        json!({ "textDocument": text_document, "contentChanges": [{ "text": "// dprint-ignore-file\nconst  a = 1;\n" }] }),
+      ),
+      request(6, "textDocument/formatting", json!({ "textDocument": text_document })),
+      notification(
+        "textDocument/didChange",
+        json!({ "textDocument": text_document, "contentChanges": [{ "text": "const  a = 1;\nlet b = (\n" }] }),
+      ),
+      request(
+        7,
+        "textDocument/onTypeFormatting",
+        json!({ "textDocument": text_document, "position": position(2, 0), "ch": "\n" }),
+      ),
+      request(8, "textDocument/hover", json!({})),
+      request(9, "shutdown", Value::Null),
+      notification("exit", Value::Null),
+      request(10, "shutdown", Value::Null),
+    ]);
+
+    let ids = responses.iter().map(|response| response["id"].as_u64().unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(responses[0]["error"]["code"], SERVER_NOT_INITIALIZED);
+    assert_eq!(responses[1]["result"]["capabilities"]["documentFormattingProvider"], true);
+    assert_eq!(
+      responses[2]["result"],
+      json!([
+        edit(position(0, 6), position(0, 7), ""),
+        edit(position(0, 11), position(0, 16), "\"🚀\""),
+        edit(position(1, 5), position(1, 5), " "),
+        edit(position(1, 6), position(1, 6), " "),
+        edit(position(1, 7), position(1, 8), "\n"),
+      ])
+    );
+    assert_eq!(responses[3]["result"], json!([edit(position(1, 0), position(1, 8), "let b = 1")]));
+    assert_eq!(responses[4]["result"], responses[3]["result"]);
+    assert_eq!(responses[5]["result"], json!([]));
+    assert_eq!(responses[6]["result"], Value::Null);
+    assert_eq!(responses[7]["error"]["code"], METHOD_NOT_FOUND);
+    assert_eq!(responses[8]["result"], Value::Null);
+  }
+
+  #[test]
+  fn uses_editor_options_without_config() {
+    let text_document = json!({ "uri": "file:///file.ts" });
+    let responses = run_session(vec![
+      request(1, "initialize", json!({ "rootUri": null })),
+      notification(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": "file:///file.ts", "text": "if (a) {\nb;\n}\n" } }),
+      ),
+      request(
+        2,
+        "textDocument/formatting",
+        json!({ "textDocument": text_document, "options": { "tabSize": 4, "insertSpaces": false } }),
+      ),
+    ]);
+    assert_eq!(responses[1]["result"], json!([edit(position(1, 0), position(1, 0), "\t")]));
+  }
+
+  #[test]
+  fn errors_for_invalid_config() {
+    let responses = run_session(vec![request(1, "initialize", json!({ "initializationOptions": { "unknown": 1 } }))]);
+    assert_eq!(responses[0]["error"]["code"], INVALID_PARAMS);
+    assert_eq!(
+      responses[0]["error"]["message"],
+      "Invalid configuration:\n  Unknown property in configuration (unknown)"
+    );
+  }
+
+  #[test]
+  fn errors_for_repeated_initialize() {
+    let responses = run_session(vec![
+      request(1, "initialize", json!({})),
+      request(2, "initialize", json!({})),
+      request(3, "textDocument/hover", json!({})),
+    ]);
+    assert_eq!(responses[1]["error"]["code"], INVALID_REQUEST);
+    assert_eq!(responses[1]["error"]["message"], "The server was already initialized.");
+    // still initialized
+    assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+  }
+
+  #[test]
+  fn errors_for_requests_after_shutdown() {
+    let text_document = json!({ "uri": "file:///file.ts" });
+    let responses = run_session(vec![
+      request(1, "initialize", json!({})),
+      notification("textDocument/didOpen", json!({ "textDocument": { "uri": "file:///file.ts", "text": "a ;" } })),
+      request(2, "shutdown", Value::Null),
+      request(3, "textDocument/formatting", json!({ "textDocument": text_document })),
+      request(4, "initialize", json!({})),
+      request(5, "shutdown", Value::Null),
+    ]);
+    assert_eq!(responses[1]["result"], Value::Null);
+    for response in &responses[2..] {
+      assert_eq!(response["error"]["code"], INVALID_REQUEST);
+      assert_eq!(response["error"]["message"], "The server was shut down.");
+    }
+  }
+}
@@ -0,0 +1,14 @@
+use dprint_core::plugins::process::get_parent_process_id_from_cli_args;
+use dprint_core::plugins::process::handle_process_stdio_messages;
+use dprint_core::plugins::process::start_parent_process_checker_task;
+use dprint_plugin_typescript::TypeScriptPluginHandler;
+
+/// Runs the plugin as a dprint process plugin, which communicates with the CLI over stdin and stdout.
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+  if let Some(parent_process_id) = get_parent_process_id_from_cli_args() {
+    start_parent_process_checker_task(parent_process_id);
+  }
+
+  handle_process_stdio_messages(TypeScriptPluginHandler).await
+}
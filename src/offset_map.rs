@@ -0,0 +1,279 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use dprint_core::formatting::Condition;
+use dprint_core::formatting::ConditionProperties;
+use rustc_hash::FxHashMap;
+use unicode_width::UnicodeWidthChar;
+
+/// Maps byte offsets in the original text to byte offsets in the formatted text.
+///
+/// The positions of the start and end of every node, token and comment are recorded
+/// while printing, so the mapping is exact at those positions even when the code was
+/// moved (ex. sorted imports). Offsets within text that kept its length are mapped
+/// relative to its start, while offsets within text that changed (ex. whitespace) are
+/// mapped to the end of the previous token. Offsets between code that was reordered
+/// have no mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetMap {
+  /// Sorted by the original offset and then the formatted offset.
+  anchors: Vec<Anchor>,
+}
+
+/// An original offset and where it is in the formatted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Anchor {
+  original: usize,
+  formatted: usize,
+  /// Whether this is the start of a node, comment or token rather than its end.
+  is_start: bool,
+}
+
+impl OffsetMap {
+  /// Gets the original and formatted offset pairs this map uses sorted by the original offset.
+  ///
+  /// The formatted offsets are not sorted when the formatter reordered code.
+  pub fn anchors(&self) -> Vec<(usize, usize)> {
+    let mut anchors = self.anchors.iter().map(|anchor| (anchor.original, anchor.formatted)).collect::<Vec<_>>();
+    anchors.dedup();
+    anchors
+  }
+
+  /// Gets the anchors that are sorted by both the original and formatted offsets.
+  pub(crate) fn monotonic_anchors(&self) -> Vec<(usize, usize)> {
+    let mut anchors: Vec<(usize, usize)> = Vec::with_capacity(self.anchors.len());
+    for anchor in self.anchors() {
+      if anchors.last().is_none_or(|last| anchor.1 >= last.1) {
+        anchors.push(anchor);
+      }
+    }
+    anchors
+  }
+
+  /// Maps a byte offset in the original text to a byte offset in the formatted text.
+  ///
+  /// Returns `None` when the offset is past the end of the text or is between code
+  /// that was reordered (ex. the whitespace between two sorted imports).
+  pub fn map_offset(&self, offset: usize) -> Option<usize> {
+    self.map_offset_preferring(offset, true)
+  }
+
+  /// Maps a byte range in the original text to a byte range in the formatted text.
+  ///
+  /// Returns `None` when either offset has no mapping or the range was reordered.
+  pub fn map_range(&self, range: Range<usize>) -> Option<Range<usize>> {
+    let start = self.map_offset_preferring(range.start, true)?;
+    let end = self.map_offset_preferring(range.end, false)?;
+    (start <= end).then_some(start..end)
+  }
+
+  /// Maps a byte offset in the formatted text back to the original text.
+  ///
+  /// This is the original offset of the closest anchor printed at or before the offset,
+  /// so it's the start of the token or node that was printed there.
+  pub(crate) fn map_formatted_offset(&self, offset: usize) -> usize {
+    self
+      .anchors
+      .iter()
+      .filter(|anchor| anchor.formatted <= offset)
+      .max_by_key(|anchor| (anchor.formatted, anchor.is_start))
+      .map(|anchor| anchor.original)
+      .unwrap_or(0)
+  }
+
+  fn map_offset_preferring(&self, offset: usize, prefer_start: bool) -> Option<usize> {
+    let index = self.anchors.partition_point(|anchor| anchor.original <= offset);
+    let previous_original = self.anchors[index.checked_sub(1)?].original;
+    // nested nodes and adjacent tokens at the same offset may be printed apart (ex. an added
+    // semi-colon), so use the innermost start or end
+    let first_index = self.anchors.partition_point(|anchor| anchor.original < previous_original);
+    let anchors = &self.anchors[first_index..index];
+    let last_start = anchors.iter().rev().find(|anchor| anchor.is_start);
+    if previous_original == offset {
+      let anchor = if prefer_start {
+        last_start
+      } else {
+        anchors.iter().find(|anchor| !anchor.is_start)
+      };
+      return Some(anchor.unwrap_or(&anchors[0]).formatted);
+    }
+
+    // the offset is within the text that starts at the previous anchor
+    let previous = last_start.unwrap_or(&anchors[anchors.len() - 1]);
+    let next = self.anchors.get(index)?;
+    if next.formatted < previous.formatted {
+      None
+    } else if next.original - previous.original == next.formatted - previous.formatted {
+      Some(previous.formatted + (offset - previous.original))
+    } else {
+      // the text between the anchors changed (ex. removed whitespace)
+      Some(previous.formatted)
+    }
+  }
+}
+
+/// Records where nodes and comments are printed in order to build an `OffsetMap`.
+#[derive(Clone, Default)]
+pub(crate) struct OffsetAnchors(Rc<RefCell<OffsetAnchorsInner>>);
+
+#[derive(Default)]
+struct OffsetAnchorsInner {
+  /// The original byte offsets, whether they are the end of a node or comment
+  /// and the line and column they were last printed at.
+  anchors: Vec<(usize, bool, Option<(u32, u32)>)>,
+  indexes: FxHashMap<(usize, bool, usize), usize>,
+}
+
+impl OffsetAnchors {
+  /// Creates a condition that records the position it's printed at for the original byte offset.
+  ///
+  /// The depth distinguishes nodes that start or end at the same offset. A node generated
+  /// more than once (ex. in the paths of a condition) shares the anchor, so only the position
+  /// it was last printed at, which is the one in the output, is used.
+  pub fn create_condition(&self, offset: usize, is_end: bool, depth: usize) -> Condition {
+    let index = {
+      let mut inner = self.0.borrow_mut();
+      let next_index = inner.anchors.len();
+      let index = *inner.indexes.entry((offset, is_end, depth)).or_insert(next_index);
+      if index == next_index {
+        inner.anchors.push((offset, is_end, None));
+      }
+      index
+    };
+    let inner = self.0.clone();
+    Condition::new(
+      "offsetAnchor",
+      ConditionProperties {
+        condition: Rc::new(move |context| {
+          let info = &context.writer_info;
+          let position = if info.expect_newline_next && !is_end {
+            // the text will be on the next line
+            (info.line_number + 1, info.indent_level as u32 * info.indent_width as u32)
+          } else {
+            (info.line_number, info.column_number)
+          };
+          inner.borrow_mut().anchors[index].2 = Some(position);
+          Some(false)
+        }),
+        true_path: None,
+        false_path: None,
+      },
+    )
+  }
+
+  /// Creates the offset map from the positions recorded while printing the formatted text.
+  ///
+  /// The `original_offset` is added to all original offsets (ex. for a stripped BOM).
+  pub fn to_offset_map(&self, original_offset: usize, original_text: &str, formatted_text: &str, indent_width: u8) -> OffsetMap {
+    let line_starts = std::iter::once(0)
+      .chain(formatted_text.match_indices('\n').map(|(index, _)| index + 1))
+      .collect::<Vec<_>>();
+    let get_offset = |(line, column): (u32, u32)| {
+      let Some(&line_start) = line_starts.get(line as usize) else {
+        return formatted_text.len();
+      };
+      let line_text = formatted_text[line_start..].split(['\r', '\n']).next().unwrap();
+      // the printer's columns are the display width where a tab is the indent width
+      let mut width = 0;
+      for (index, c) in line_text.char_indices() {
+        if width >= column {
+          return line_start + index;
+        }
+        width += match c {
+          '\t' => indent_width as u32,
+          c => c.width().unwrap_or(0) as u32,
+        };
+      }
+      line_start + line_text.len()
+    };
+
+    let inner = self.0.borrow();
+    let mut anchors = Vec::with_capacity(inner.anchors.len() + 3);
+    // the stripped BOM
+    anchors.push(Anchor {
+      original: 0,
+      formatted: 0,
+      is_start: true,
+    });
+    let mut push_anchor = |original: usize, formatted: usize, is_start: bool| {
+      anchors.push(Anchor {
+        original: original_offset + original,
+        formatted,
+        is_start,
+      })
+    };
+    push_anchor(0, 0, true);
+    for &(offset, is_end, position) in &inner.anchors {
+      if let Some(position) = position {
+        push_anchor(offset, get_offset(position), !is_end);
+      }
+    }
+    push_anchor(original_text.len(), formatted_text.len(), false);
+    anchors.sort_unstable();
+    anchors.dedup();
+
+    // add anchors for the tokens between the nodes (ex. punctuation and keywords)
+    let mut token_anchors = Vec::new();
+    for window in anchors.windows(2) {
+      let (start, end) = (window[0], window[1]);
+      if start.original >= original_offset && end.formatted >= start.formatted {
+        let original_gap = &original_text[start.original - original_offset..end.original - original_offset];
+        let formatted_gap = &formatted_text[start.formatted..end.formatted];
+        token_anchors.extend(
+          get_token_anchors(original_gap, formatted_gap)
+            .into_iter()
+            .map(|(original, formatted, is_start)| Anchor {
+              original: start.original + original,
+              formatted: start.formatted + formatted,
+              is_start,
+            }),
+        );
+      }
+    }
+    if !token_anchors.is_empty() {
+      anchors.extend(token_anchors);
+      anchors.sort_unstable();
+      anchors.dedup();
+    }
+    OffsetMap { anchors }
+  }
+}
+
+/// Gets anchors at the boundaries of the tokens in the text between two anchors that
+/// start or end the same way when ignoring whitespace.
+fn get_token_anchors(original: &str, formatted: &str) -> Vec<(usize, usize, bool)> {
+  let original_chars = original.char_indices().filter(|(_, c)| !c.is_whitespace()).collect::<Vec<_>>();
+  let formatted_chars = formatted.char_indices().filter(|(_, c)| !c.is_whitespace()).collect::<Vec<_>>();
+  let max_len = original_chars.len().min(formatted_chars.len());
+  let prefix_len = (0..max_len).take_while(|&i| original_chars[i].1 == formatted_chars[i].1).count();
+  let suffix_len = (0..max_len - prefix_len)
+    .take_while(|&i| original_chars[original_chars.len() - 1 - i].1 == formatted_chars[formatted_chars.len() - 1 - i].1)
+    .count();
+  let is_whitespace = |c: Option<char>| c.is_none_or(|c| c.is_whitespace());
+  let mut anchors = Vec::new();
+  let mut add_anchors = |original_index: usize, formatted_index: usize, c: char, is_changed_before: bool, is_changed_after: bool| {
+    let end_offset = c.len_utf8();
+    if is_changed_before || is_whitespace(original[..original_index].chars().next_back()) || is_whitespace(formatted[..formatted_index].chars().next_back()) {
+      anchors.push((original_index, formatted_index, true));
+    }
+    if is_changed_after
+      || is_whitespace(original[original_index + end_offset..].chars().next())
+      || is_whitespace(formatted[formatted_index + end_offset..].chars().next())
+    {
+      anchors.push((original_index + end_offset, formatted_index + end_offset, false));
+    }
+  };
+  for i in 0..prefix_len {
+    let ((original_index, c), (formatted_index, _)) = (original_chars[i], formatted_chars[i]);
+    add_anchors(original_index, formatted_index, c, false, i + 1 == prefix_len && prefix_len < max_len);
+  }
+  for i in 0..suffix_len {
+    let ((original_index, c), (formatted_index, _)) = (
+      original_chars[original_chars.len() - suffix_len + i],
+      formatted_chars[formatted_chars.len() - suffix_len + i],
+    );
+    add_anchors(original_index, formatted_index, c, i == 0, false);
+  }
+  anchors
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::configuration::GlobalConfiguration;
+use dprint_core::plugins::FileMatchingInfo;
+use dprint_core::plugins::FormatResult;
+use dprint_core::plugins::PluginInfo;
+use dprint_core::plugins::PluginResolveConfigurationResult;
+
+use super::configuration::resolve_config;
+use super::configuration::Configuration;
+use super::format_text_with_warnings;
+use super::ExternalFormatRequest;
+use super::FormatTextOptions;
+
+/// The dprint plugin, which is implemented as a Wasm plugin and as a process plugin.
+pub struct TypeScriptPluginHandler;
+
+impl TypeScriptPluginHandler {
+  pub(crate) fn resolve_plugin_config(config: ConfigKeyMap, global_config: &GlobalConfiguration) -> PluginResolveConfigurationResult<Configuration> {
+    let config = resolve_config(config, global_config);
+    PluginResolveConfigurationResult {
+      config: config.config,
+      diagnostics: config.diagnostics,
+      file_matching: FileMatchingInfo {
+        file_extensions: vec![
+          String::from("ts"),
+          String::from("tsx"),
+          String::from("js"),
+          String::from("jsx"),
+          String::from("mjs"),
+          String::from("cjs"),
+          String::from("mts"),
+          String::from("cts"),
+          String::from("gts"),
+          String::from("gjs"),
+        ],
+        file_names: vec![],
+      },
+    }
+  }
+
+  pub(crate) fn get_plugin_info() -> PluginInfo {
+    let version = env!("CARGO_PKG_VERSION").to_string();
+    PluginInfo {
+      name: env!("CARGO_PKG_NAME").to_string(),
+      version: version.clone(),
+      config_key: "typescript".to_string(),
+      help_url: "https://dprint.dev/plugins/typescript".to_string(),
+      config_schema_url: format!("https://plugins.dprint.dev/dprint/dprint-plugin-typescript/{}/schema.json", version),
+      update_url: Some("https://plugins.dprint.dev/dprint/dprint-plugin-typescript/latest.json".to_string()),
+    }
+  }
+
+  pub(crate) fn get_license_text() -> String {
+    std::str::from_utf8(include_bytes!("../LICENSE")).unwrap().into()
+  }
+}
+
+/// Formats a file for the host, leaving the embedded languages that fail to format as-is
+/// instead of failing the whole file.
+///
+/// The failures are written to stderr, which the dprint CLI shows for process plugins.
+#[allow(clippy::print_stderr)] // the plugin's stderr is its only way to report to the CLI
+pub(crate) fn format_for_host(options: FormatTextOptions) -> FormatResult {
+  let file_path = options.path;
+  let result = format_text_with_warnings(options)?;
+  for warning in &result.warnings {
+    eprintln!("Warning formatting {}: {}", file_path.display(), warning);
+  }
+  Ok(result.text.map(|text| text.into_bytes()))
+}
+
+/// Gets the file path and override configuration to format an embedded language with the plugin
+/// the host has for a file with the language as its extension (ex. `embedded.css`), using the
+/// width that is available to the embedded text.
+pub(crate) fn get_embedded_host_format_args(request: &ExternalFormatRequest) -> (PathBuf, ConfigKeyMap) {
+  let file_path = PathBuf::from(format!("embedded.{}", request.language));
+  let mut override_config = ConfigKeyMap::new();
+  override_config.insert("lineWidth".to_string(), ConfigKeyValue::from_i32(request.line_width as i32));
+  (file_path, override_config)
+}
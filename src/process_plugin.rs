@@ -0,0 +1,177 @@
+use dprint_core::async_runtime::async_trait;
+use dprint_core::async_runtime::spawn_blocking;
+use dprint_core::async_runtime::LocalBoxFuture;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::GlobalConfiguration;
+use dprint_core::plugins::AsyncPluginHandler;
+use dprint_core::plugins::CheckConfigUpdatesMessage;
+use dprint_core::plugins::ConfigChange;
+use dprint_core::plugins::FormatRequest;
+use dprint_core::plugins::FormatResult;
+use dprint_core::plugins::HostFormatRequest;
+use dprint_core::plugins::PluginInfo;
+use dprint_core::plugins::PluginResolveConfigurationResult;
+
+use super::configuration::get_config_updates;
+use super::configuration::Configuration;
+use super::plugin_handler::format_for_host;
+use super::plugin_handler::get_embedded_host_format_args;
+use super::ExternalFormatRequest;
+use super::TypeScriptPluginHandler;
+
+#[async_trait(?Send)]
+impl AsyncPluginHandler for TypeScriptPluginHandler {
+  type Configuration = Configuration;
+
+  fn plugin_info(&self) -> PluginInfo {
+    TypeScriptPluginHandler::get_plugin_info()
+  }
+
+  fn license_text(&self) -> String {
+    TypeScriptPluginHandler::get_license_text()
+  }
+
+  async fn resolve_config(&self, config: ConfigKeyMap, global_config: GlobalConfiguration) -> PluginResolveConfigurationResult<Configuration> {
+    TypeScriptPluginHandler::resolve_plugin_config(config, &global_config)
+  }
+
+  async fn check_config_updates(&self, message: CheckConfigUpdatesMessage) -> anyhow::Result<Vec<ConfigChange>> {
+    Ok(get_config_updates(message.old_version.as_deref(), &message.config))
+  }
+
+  async fn format(
+    &self,
+    request: FormatRequest<Configuration>,
+    mut format_with_host: impl FnMut(HostFormatRequest) -> LocalBoxFuture<'static, FormatResult> + 'static,
+  ) -> FormatResult {
+    if request.token.is_cancelled() {
+      return Ok(None);
+    }
+
+    // The file is formatted on a blocking thread so files are formatted in parallel. Embedded
+    // languages are sent back to this task to be formatted with the host.
+    let (host_request_tx, mut host_request_rx) = tokio::sync::mpsc::unbounded_channel::<(HostFormatRequest, std::sync::mpsc::Sender<FormatResult>)>();
+    let mut format_task = spawn_blocking(move || {
+      let file_text = String::from_utf8(request.file_bytes)?;
+      let external_formatter = |embedded_request: ExternalFormatRequest| {
+        // leave the remaining embedded languages as-is since the result won't be used
+        if request.token.is_cancelled() {
+          return Ok(None);
+        }
+        let (file_path, override_config) = get_embedded_host_format_args(&embedded_request);
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        let host_request = HostFormatRequest {
+          file_path,
+          file_bytes: embedded_request.text.into_bytes(),
+          range: None,
+          override_config,
+          token: request.token.clone(),
+        };
+        host_request_tx
+          .send((host_request, result_tx))
+          .map_err(|_| anyhow::anyhow!("Stopped formatting with the host."))?;
+        match result_rx.recv()?? {
+          Some(bytes) => Ok(Some(String::from_utf8(bytes)?)),
+          None => Ok(None),
+        }
+      };
+      format_for_host(super::FormatTextOptions {
+        path: &request.file_path,
+        extension: None,
+        text: file_text,
+        config: &request.config,
+        external_formatter: Some(&external_formatter),
+      })
+    });
+
+    loop {
+      tokio::select! {
+        result = &mut format_task => return result?,
+        Some((host_request, result_tx)) = host_request_rx.recv() => {
+          // the formatting thread is waiting on the result, so it's fine if it stopped waiting
+          let _ = result_tx.send(format_with_host(host_request).await);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::sync::Arc;
+
+  use dprint_core::plugins::CancellationToken;
+  use dprint_core::plugins::FormatRange;
+
+  use super::*;
+  use crate::configuration::ConfigurationBuilder;
+
+  #[derive(Debug, Default)]
+  struct TestCancellationToken(AtomicBool);
+
+  impl CancellationToken for TestCancellationToken {
+    fn is_cancelled(&self) -> bool {
+      self.0.load(Ordering::SeqCst)
+    }
+
+    fn wait_cancellation(&self) -> LocalBoxFuture<'static, ()> {
+      Box::pin(std::future::pending())
+    }
+  }
+
+  fn create_request(file_text: &str, token: Arc<TestCancellationToken>) -> FormatRequest<Configuration> {
+    FormatRequest {
+      file_path: PathBuf::from("file.ts"),
+      file_bytes: file_text.as_bytes().to_vec(),
+      config_id: dprint_core::plugins::FormatConfigId::from_raw(1),
+      config: Arc::new(ConfigurationBuilder::new().build()),
+      range: FormatRange::default(),
+      token,
+    }
+  }
+
+  async fn format(token: Arc<TestCancellationToken>, host_format_count: Arc<AtomicUsize>) -> FormatResult {
+    let request = create_request("const a = css`a`;\nconst b = css`b`;\n", token.clone());
+    TypeScriptPluginHandler
+      .format(request, move |host_request| {
+        host_format_count.fetch_add(1, Ordering::SeqCst);
+        // cancel while formatting the first embedded language
+        token.0.store(true, Ordering::SeqCst);
+        Box::pin(async move { Ok(Some(host_request.file_bytes)) })
+      })
+      .await
+  }
+
+  #[tokio::test]
+  async fn stops_formatting_when_cancelled() {
+    let host_format_count = Arc::new(AtomicUsize::new(0));
+    let token = Arc::new(TestCancellationToken::default());
+    token.0.store(true, Ordering::SeqCst);
+    assert!(format(token, host_format_count.clone()).await.unwrap().is_none());
+    assert_eq!(host_format_count.load(Ordering::SeqCst), 0);
+
+    format(Arc::new(TestCancellationToken::default()), host_format_count.clone()).await.unwrap();
+    assert_eq!(host_format_count.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test]
+  async fn keeps_templates_the_host_fails_on() {
+    let request = create_request("const  a = css`a{}`;\nconst  b = css`b{}`;\n", Default::default());
+    let result = TypeScriptPluginHandler
+      .format(request, |host_request| {
+        Box::pin(async move {
+          if host_request.file_bytes == b"a{}" {
+            anyhow::bail!("Some error.");
+          }
+          Ok(Some(b"b {}".to_vec()))
+        })
+      })
+      .await
+      .unwrap();
+    assert_eq!(String::from_utf8(result.unwrap()).unwrap(), "const a = css`a{}`;\nconst b = css`\n  b {}\n`;\n");
+  }
+}
@@ -1,13 +1,19 @@
-use anyhow::anyhow;
 use anyhow::bail;
 use anyhow::Result;
 use deno_ast::swc::parser::error::SyntaxError;
+use deno_ast::swc::parser::token::Token;
 use deno_ast::swc::parser::Syntax;
 use deno_ast::ModuleSpecifier;
+use deno_ast::ParseDiagnostic;
 use deno_ast::ParsedSource;
+use deno_ast::TokenOrComment;
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
 
+use crate::error::FormatError;
+use crate::error::SyntaxDiagnostic;
+
 pub fn parse_swc_ast(file_path: &Path, file_extension: Option<&str>, file_text: Arc<str>) -> Result<ParsedSource> {
   match parse_inner(file_path, file_extension, file_text.clone()) {
     Ok(result) => Ok(result),
@@ -33,27 +39,41 @@ fn parse_inner(file_path: &Path, file_extension: Option<&str>, text: Arc<str>) -
   Ok(parsed_source)
 }
 
+/// Parses the text the same way as the provided parsed source (ex. for parsing the formatted text).
+pub fn reparse_swc_ast(parsed_source: &ParsedSource, text: Arc<str>) -> Result<ParsedSource> {
+  let parsed_source = parse_with_media_type(parsed_source.specifier().clone(), parsed_source.media_type(), text)?;
+  ensure_no_specific_syntax_errors(&parsed_source)?;
+  Ok(parsed_source)
+}
+
 fn parse_inner_no_diagnostic_check(file_path: &Path, file_extension: Option<&str>, text: Arc<str>) -> Result<ParsedSource> {
-  let media_type = if let Some(file_extension) = file_extension {
-    deno_ast::MediaType::from_path(&file_path.with_extension(file_extension))
-  } else {
-    deno_ast::MediaType::from_path(file_path)
-  };
+  let media_type = get_media_type(file_path, file_extension);
+  parse_with_media_type(path_to_specifier(file_path)?, media_type, text)
+}
 
+pub fn parse_with_media_type(specifier: ModuleSpecifier, media_type: deno_ast::MediaType, text: Arc<str>) -> Result<ParsedSource> {
   let mut syntax = deno_ast::get_syntax(media_type);
   if let Syntax::Es(es) = &mut syntax {
     // support decorators in js
     es.decorators = true;
   }
   deno_ast::parse_program(deno_ast::ParseParams {
-    specifier: path_to_specifier(file_path)?,
+    specifier,
     capture_tokens: true,
     maybe_syntax: Some(syntax),
     media_type,
     scope_analysis: false,
     text,
   })
-  .map_err(|diagnostic| anyhow!("{:#}", &diagnostic))
+  .map_err(|diagnostic| FormatError::Syntax(vec![SyntaxDiagnostic::new(diagnostic)]).into())
+}
+
+fn get_media_type(file_path: &Path, file_extension: Option<&str>) -> deno_ast::MediaType {
+  if let Some(file_extension) = file_extension {
+    deno_ast::MediaType::from_path(&file_path.with_extension(file_extension))
+  } else {
+    deno_ast::MediaType::from_path(file_path)
+  }
 }
 
 fn path_to_specifier(path: &Path) -> Result<ModuleSpecifier> {
@@ -105,8 +125,105 @@ fn from_file_path_wasm(path: &Path) -> Option<ModuleSpecifier> {
   ModuleSpecifier::parse(&format!("file:///{}", parts.join("/"))).ok()
 }
 
+/// Parses the file even when it has syntax errors by blanking out the top-level
+/// regions of the text that have errors until it parses.
+///
+/// Returns the parsed source of the blanked text along with the byte ranges
+/// of the text that have syntax errors.
+pub fn parse_swc_ast_lenient(file_path: &Path, file_extension: Option<&str>, file_text: &str) -> Result<(ParsedSource, Vec<Range<usize>>)> {
+  const MAX_ATTEMPTS: usize = 50;
+  let media_type = get_media_type(file_path, file_extension);
+  let mut text = file_text.to_string();
+  let mut error_ranges = Vec::new();
+  loop {
+    let err = match parse_inner_no_diagnostic_check(file_path, file_extension, text.clone().into()) {
+      Ok(parsed_source) => {
+        let source_start = parsed_source.range().start;
+        for diagnostic in get_specific_syntax_errors(&parsed_source) {
+          let pos = diagnostic.range.start - source_start;
+          error_ranges.push(get_top_level_region(&text, media_type, pos));
+        }
+        return Ok((parsed_source, error_ranges));
+      }
+      Err(err) => err,
+    };
+    let Some(FormatError::Syntax(diagnostics)) = err.downcast_ref::<FormatError>() else {
+      return Err(err);
+    };
+    let region = get_top_level_region(&text, media_type, diagnostics[0].location.range.start);
+    if error_ranges.len() >= MAX_ATTEMPTS || text[region.clone()].trim().is_empty() {
+      return Err(err);
+    }
+    text = blank_range(&text, region.clone());
+    error_ranges.push(region);
+  }
+}
+
+/// Gets the range of the top-level statement(s) surrounding the provided byte position.
+///
+/// This only uses the tokens because the text may not parse.
+fn get_top_level_region(text: &str, media_type: deno_ast::MediaType, pos: usize) -> Range<usize> {
+  let mut depth = 0usize;
+  let mut start = 0;
+  let mut end = None;
+  let mut last_token_end = 0;
+  let mut last_token_was_semi = false;
+  for item in deno_ast::lex(text, media_type) {
+    let TokenOrComment::Token(token) = &item.inner else {
+      continue;
+    };
+    let is_statement_start = depth == 0 && (last_token_was_semi || item.range.start == 0 || text[..item.range.start].ends_with('\n'));
+    if is_statement_start {
+      if item.range.start <= pos {
+        start = item.range.start;
+      } else {
+        end = Some(last_token_end);
+        break;
+      }
+    }
+    match token {
+      Token::LParen | Token::LBrace | Token::LBracket | Token::DollarLBrace => depth += 1,
+      Token::RParen | Token::RBrace | Token::RBracket => depth = depth.saturating_sub(1),
+      _ => {}
+    }
+    last_token_end = item.range.end;
+    last_token_was_semi = matches!(token, Token::Semi);
+  }
+  start..std::cmp::max(end.unwrap_or(last_token_end), pos)
+}
+
+/// Replaces the text in the range with spaces while keeping the newlines and byte positions.
+fn blank_range(text: &str, range: Range<usize>) -> String {
+  let mut new_text = String::with_capacity(text.len());
+  new_text.push_str(&text[..range.start]);
+  for c in text[range.clone()].chars() {
+    if c == '\n' || c == '\r' {
+      new_text.push(c);
+    } else {
+      new_text.extend(std::iter::repeat_n(' ', c.len_utf8()));
+    }
+  }
+  new_text.push_str(&text[range.end..]);
+  new_text
+}
+
 pub fn ensure_no_specific_syntax_errors(parsed_source: &ParsedSource) -> Result<()> {
-  let diagnostics = parsed_source
+  let diagnostics = get_specific_syntax_errors(parsed_source)
+    .into_iter()
+    .cloned()
+    .map(SyntaxDiagnostic::new)
+    .collect::<Vec<_>>();
+
+  if diagnostics.is_empty() {
+    Ok(())
+  } else {
+    Err(FormatError::Syntax(diagnostics).into())
+  }
+}
+
+/// Gets the non-fatal diagnostics that should still prevent formatting.
+fn get_specific_syntax_errors(parsed_source: &ParsedSource) -> Vec<&ParseDiagnostic> {
+  parsed_source
     .diagnostics()
     .iter()
     .filter(|e| {
@@ -140,20 +257,7 @@ pub fn ensure_no_specific_syntax_errors(parsed_source: &ParsedSource) -> Result<
         SyntaxError::TS1185
       )
     })
-    .collect::<Vec<_>>();
-
-  if diagnostics.is_empty() {
-    Ok(())
-  } else {
-    let mut final_message = String::new();
-    for diagnostic in diagnostics {
-      if !final_message.is_empty() {
-        final_message.push_str("\n\n");
-      }
-      final_message.push_str(&format!("{diagnostic}"));
-    }
-    bail!("{}", final_message)
-  }
+    .collect()
 }
 
 fn get_lowercase_extension(file_path: &Path) -> Option<String> {
@@ -263,6 +367,19 @@ mod tests {
     );
   }
 
+  #[test]
+  fn should_provide_syntax_error_locations() {
+    let err = parse_swc_ast(&PathBuf::from("./test.ts"), None, "test;\nas#;".into()).err().unwrap();
+    let FormatError::Syntax(diagnostics) = err.downcast_ref::<FormatError>().unwrap() else {
+      unreachable!();
+    };
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].location.specifier.as_str(), "file:///test.ts");
+    assert_eq!(diagnostics[0].location.line_number, 2);
+    assert_eq!(diagnostics[0].location.column_number, 3);
+    assert_eq!(diagnostics[0].location.range, 8..9);
+  }
+
   fn run_fatal_diagnostic_test(file_path: &str, text: &str, expected: &str) {
     let file_path = PathBuf::from(file_path);
     assert_eq!(parse_swc_ast(&file_path, None, text.into()).err().unwrap().to_string(), expected);
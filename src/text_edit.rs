@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+use super::offset_map::OffsetMap;
+
+/// A change to apply to the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+  /// Byte range in the original text to replace.
+  pub range: Range<usize>,
+  pub new_text: String,
+}
+
+/// Gets the minimal edits to go from the original text to the formatted text.
+///
+/// Only the text between the anchors of the offset map is compared, which keeps
+/// this linear and prevents edits from spanning unrelated tokens or comments.
+pub(crate) fn get_text_edits(original_text: &str, formatted_text: &str, offset_map: &OffsetMap) -> Vec<TextEdit> {
+  let mut edits: Vec<TextEdit> = Vec::new();
+  let mut previous = (0, 0);
+  for anchor in offset_map.monotonic_anchors() {
+    let original = &original_text[previous.0..anchor.0];
+    let formatted = &formatted_text[previous.1..anchor.1];
+    if original != formatted {
+      let prefix_len = get_common_prefix_len(original, formatted);
+      let suffix_len = get_common_suffix_len(&original[prefix_len..], &formatted[prefix_len..]);
+      let range = previous.0 + prefix_len..anchor.0 - suffix_len;
+      let new_text = &formatted[prefix_len..formatted.len() - suffix_len];
+      match edits.last_mut() {
+        Some(last) if last.range.end == range.start => {
+          last.range.end = range.end;
+          last.new_text.push_str(new_text);
+        }
+        _ => edits.push(TextEdit {
+          range,
+          new_text: new_text.to_string(),
+        }),
+      }
+    }
+    previous = anchor;
+  }
+  edits
+}
+
+fn get_common_prefix_len(a: &str, b: &str) -> usize {
+  a.char_indices()
+    .zip(b.chars())
+    .find(|((_, a_char), b_char)| a_char != b_char)
+    .map(|((index, _), _)| index)
+    .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+fn get_common_suffix_len(a: &str, b: &str) -> usize {
+  a.chars()
+    .rev()
+    .zip(b.chars().rev())
+    .take_while(|(a_char, b_char)| a_char == b_char)
+    .map(|(c, _)| c.len_utf8())
+    .sum()
+}
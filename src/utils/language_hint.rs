@@ -0,0 +1,25 @@
+use std::collections::BTreeMap;
+
+/// Gets the embedded language a block comment's text hints at (ex. `/* sql */`)
+/// from the configured tagged template languages.
+pub fn get_language_from_comment_hint<'a>(languages: &'a BTreeMap<String, String>, comment_text: &str) -> Option<&'a str> {
+  let hint = comment_text.trim();
+  languages
+    .get(hint)
+    .or_else(|| languages.get(&hint.to_lowercase()))
+    .map(|language| language.as_str())
+}
+
+/// Gets the embedded language of a tagged template from the path of its tag (ex. `styled.div`)
+/// and the configured tagged template languages.
+///
+/// A member path or call also matches the start of its path (ex. `styled` matches `styled.div` and `styled(Button)`).
+pub fn get_language_from_tag_path<'a>(languages: &'a BTreeMap<String, String>, mut path: String) -> Option<&'a str> {
+  loop {
+    if let Some(language) = languages.get(&path) {
+      return Some(language);
+    }
+    let index = path.rfind('.')?;
+    path.truncate(index);
+  }
+}
@@ -1,12 +1,16 @@
 mod char_iterator;
 mod file_text_has_ignore_comment;
 mod is_prefix_semi_colon_insertion_char;
+mod language_hint;
+mod panic_message;
 mod stack;
 mod string_utils;
 mod vec_map;
 
 pub use file_text_has_ignore_comment::*;
 pub use is_prefix_semi_colon_insertion_char::*;
+pub use language_hint::*;
+pub use panic_message::*;
 pub use stack::*;
 pub use string_utils::*;
 pub use vec_map::*;
@@ -0,0 +1,10 @@
+use std::any::Any;
+
+/// Gets the message of a panic caught with `catch_unwind`.
+pub fn get_panic_message(panic: &(dyn Any + Send)) -> String {
+  panic
+    .downcast_ref::<&str>()
+    .map(|message| message.to_string())
+    .or_else(|| panic.downcast_ref::<String>().cloned())
+    .unwrap_or_else(|| "Unknown panic.".to_string())
+}
@@ -18,6 +18,10 @@ impl<T> Stack<T> {
     self.items.push(item);
   }
 
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
   pub fn peek(&self) -> Option<&T> {
     self.items.last()
   }
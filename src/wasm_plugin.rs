@@ -1,9 +1,10 @@
+use std::cell::RefCell;
+
 use dprint_core::configuration::ConfigKeyMap;
 use dprint_core::configuration::GlobalConfiguration;
 use dprint_core::generate_plugin_code;
 use dprint_core::plugins::CheckConfigUpdatesMessage;
 use dprint_core::plugins::ConfigChange;
-use dprint_core::plugins::FileMatchingInfo;
 use dprint_core::plugins::FormatResult;
 use dprint_core::plugins::PluginInfo;
 use dprint_core::plugins::PluginResolveConfigurationResult;
@@ -11,64 +12,61 @@ use dprint_core::plugins::SyncFormatRequest;
 use dprint_core::plugins::SyncHostFormatRequest;
 use dprint_core::plugins::SyncPluginHandler;
 
-use super::configuration::resolve_config;
+use super::configuration::get_config_updates;
 use super::configuration::Configuration;
-
-struct TypeScriptPluginHandler;
+use super::plugin_handler::format_for_host;
+use super::plugin_handler::get_embedded_host_format_args;
+use super::ExternalFormatRequest;
+use super::TypeScriptPluginHandler;
 
 impl SyncPluginHandler<Configuration> for TypeScriptPluginHandler {
   fn resolve_config(&mut self, config: ConfigKeyMap, global_config: &GlobalConfiguration) -> PluginResolveConfigurationResult<Configuration> {
-    let config = resolve_config(config, global_config);
-    PluginResolveConfigurationResult {
-      config: config.config,
-      diagnostics: config.diagnostics,
-      file_matching: FileMatchingInfo {
-        file_extensions: vec![
-          String::from("ts"),
-          String::from("tsx"),
-          String::from("js"),
-          String::from("jsx"),
-          String::from("mjs"),
-          String::from("cjs"),
-          String::from("mts"),
-          String::from("cts"),
-        ],
-        file_names: vec![],
-      },
-    }
+    TypeScriptPluginHandler::resolve_plugin_config(config, global_config)
   }
 
-  fn check_config_updates(&self, _message: CheckConfigUpdatesMessage) -> Result<Vec<ConfigChange>, anyhow::Error> {
-    Ok(Vec::new())
+  fn check_config_updates(&self, message: CheckConfigUpdatesMessage) -> Result<Vec<ConfigChange>, anyhow::Error> {
+    Ok(get_config_updates(message.old_version.as_deref(), &message.config))
   }
 
   fn plugin_info(&mut self) -> PluginInfo {
-    let version = env!("CARGO_PKG_VERSION").to_string();
-    PluginInfo {
-      name: env!("CARGO_PKG_NAME").to_string(),
-      version: version.clone(),
-      config_key: "typescript".to_string(),
-      help_url: "https://dprint.dev/plugins/typescript".to_string(),
-      config_schema_url: format!("https://plugins.dprint.dev/dprint/dprint-plugin-typescript/{}/schema.json", version),
-      update_url: Some("https://plugins.dprint.dev/dprint/dprint-plugin-typescript/latest.json".to_string()),
-    }
+    TypeScriptPluginHandler::get_plugin_info()
   }
 
   fn license_text(&mut self) -> String {
-    std::str::from_utf8(include_bytes!("../LICENSE")).unwrap().into()
+    TypeScriptPluginHandler::get_license_text()
   }
 
-  fn format(&mut self, request: SyncFormatRequest<Configuration>, _format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult) -> FormatResult {
+  fn format(&mut self, request: SyncFormatRequest<Configuration>, format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult) -> FormatResult {
     let file_text = String::from_utf8(request.file_bytes)?;
-    super::format_text(super::FormatTextOptions {
+    let format_with_host = RefCell::new(format_with_host);
+    let external_formatter = |request: ExternalFormatRequest| format_embedded_with_host(request, &mut *format_with_host.borrow_mut());
+    format_for_host(super::FormatTextOptions {
       path: request.file_path,
       extension: None,
       text: file_text,
       config: request.config,
-      // todo: support this in Wasm
-      external_formatter: None,
+      external_formatter: Some(&external_formatter),
     })
-    .map(|maybe_text| maybe_text.map(|t| t.into_bytes()))
+  }
+}
+
+/// Formats an embedded language with the host.
+///
+/// The text is left as-is when the host doesn't change it, which includes when it has no plugin for the language.
+fn format_embedded_with_host(
+  request: ExternalFormatRequest,
+  format_with_host: &mut impl FnMut(SyncHostFormatRequest) -> FormatResult,
+) -> anyhow::Result<Option<String>> {
+  let (file_path, override_config) = get_embedded_host_format_args(&request);
+  let maybe_bytes = format_with_host(SyncHostFormatRequest {
+    file_path: &file_path,
+    file_bytes: request.text.as_bytes(),
+    range: None,
+    override_config: &override_config,
+  })?;
+  match maybe_bytes {
+    Some(bytes) => Ok(Some(String::from_utf8(bytes)?)),
+    None => Ok(None),
   }
 }
 
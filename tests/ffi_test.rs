@@ -0,0 +1,51 @@
+//! Calls the C API through its exported symbols like a C program would.
+#![cfg(feature = "ffi")]
+
+use std::ffi::c_char;
+use std::ffi::CStr;
+
+// links the library that exports the symbols
+use dprint_plugin_typescript as _;
+
+#[repr(C)]
+struct DprintTsConfig {
+  _private: [u8; 0],
+}
+
+extern "C" {
+  fn dprint_ts_config_new(json_text: *const u8, json_text_len: usize) -> *mut DprintTsConfig;
+  fn dprint_ts_config_free(config: *mut DprintTsConfig);
+  fn dprint_ts_format(
+    config: *const DprintTsConfig,
+    file_path: *const c_char,
+    text: *const u8,
+    text_len: usize,
+    out_text: *mut *mut u8,
+    out_text_len: *mut usize,
+  ) -> i32;
+  fn dprint_ts_text_free(text: *mut u8, text_len: usize);
+  fn dprint_ts_last_error() -> *const c_char;
+}
+
+#[test]
+fn formats_through_exported_symbols() {
+  unsafe {
+    let json = r#"{ "indentWidth": 4 }"#;
+    let config = dprint_ts_config_new(json.as_ptr(), json.len());
+    assert!(!config.is_null());
+
+    let text = "if (a) {\nb;\n}";
+    let mut out_text = std::ptr::null_mut();
+    let mut out_text_len = 0;
+    let status = dprint_ts_format(config, c"file.ts".as_ptr(), text.as_ptr(), text.len(), &mut out_text, &mut out_text_len);
+    assert_eq!(status, 1);
+    assert_eq!(std::slice::from_raw_parts(out_text, out_text_len), b"if (a) {\n    b;\n}\n");
+    dprint_ts_text_free(out_text, out_text_len);
+
+    let text = "const a = ;";
+    let status = dprint_ts_format(config, c"file.ts".as_ptr(), text.as_ptr(), text.len(), &mut out_text, &mut out_text_len);
+    assert_eq!(status, -1);
+    assert!(CStr::from_ptr(dprint_ts_last_error()).to_str().unwrap().starts_with("Expression expected"));
+    dprint_ts_config_free(config);
+  }
+}
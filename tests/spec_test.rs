@@ -8,12 +8,12 @@ use dprint_development::*;
 use dprint_plugin_typescript::configuration::*;
 use dprint_plugin_typescript::*;
 
-fn external_formatter(media_type: MediaType, text: String, config: &Configuration) -> Result<Option<String>> {
-  match media_type {
-    MediaType::Css => format_embedded_css(&text, config),
-    MediaType::Html => format_html(&text, config),
-    MediaType::Sql => format_sql(&text, config),
-    _ => unreachable!(),
+fn external_formatter(request: ExternalFormatRequest) -> Result<Option<String>> {
+  match request.media_type {
+    MediaType::Css => format_embedded_css(&request.text, request.config),
+    MediaType::Html => format_html(&request.text, request.config, request.line_width),
+    MediaType::Sql => format_sql(&request.text, request.config),
+    _ => Ok(None),
   }
 }
 
@@ -51,11 +51,12 @@ fn format_embedded_css(text: &str, config: &Configuration) -> Result<Option<Stri
   Ok(Some(buf.join("\n").to_string()))
 }
 
-fn format_html(text: &str, config: &Configuration) -> Result<Option<String>> {
+fn format_html(text: &str, config: &Configuration, line_width: u32) -> Result<Option<String>> {
   use markup_fmt::config;
   let options = config::FormatOptions {
     layout: config::LayoutOptions {
       indent_width: config.indent_width as usize,
+      print_width: line_width as usize,
       ..Default::default()
     },
     ..Default::default()